@@ -0,0 +1,912 @@
+//! Codeowners provides interfaces for resolving owners of paths within code
+//! repositories using
+//! Github [CODEOWNERS](https://help.github.com/articles/about-codeowners/)
+//! files
+//!
+//! # Examples
+//!
+//! Typical use involves resolving a CODEOWNERS file, parsing it,
+//! then querying target paths
+//!
+//! ```no_run
+//! extern crate codeowners;
+//! use std::env;
+//!
+//! fn main() {
+//!   if let (Some(owners_file), Some(path)) =
+//!      (env::args().nth(1), env::args().nth(2)) {
+//!      let owners = codeowners::from_path(owners_file);
+//!      match owners.of(&path) {
+//!        None => println!("{} is up for adoption", path),
+//!        Some(owners) => {
+//!           for owner in owners {
+//!             println!("{}", owner);
+//!           }
+//!        }
+//!      }
+//!   }
+//! }
+//! ```
+//!
+//! Every resolved path can be traced back to the CODEOWNERS line which
+//! determined its owners, and lines which could not be fully understood
+//! are reported as diagnostics instead of being silently ignored
+//!
+//! ```rust
+//! let owners = codeowners::from_reader("/docs/ @org/docs not-an-owner".as_bytes());
+//! let rule = owners.rule_of("docs/README.md").unwrap();
+//! assert_eq!(rule.line, 1);
+//! assert_eq!(rule.raw_pattern, "/docs/");
+//! assert_eq!(owners.diagnostics().len(), 1);
+//! ```
+//!
+//! GitLab sections are parsed as well. Every section resolves owners
+//! independently, so a path can have a rule in each of them
+//!
+//! ```rust
+//! let owners = codeowners::from_reader("[Docs][2] @org/docs\n*.md\n[Backend]\n*.md @bob".as_bytes());
+//! let rules = owners.section_rules_of("README.md");
+//! assert_eq!(rules.len(), 2);
+//! assert_eq!(rules[0].owners, vec![codeowners::Owner::Team("@org/docs".into())]);
+//! assert_eq!(owners.section("docs").unwrap().approvals, 2);
+//! ```
+#![warn(missing_docs)]
+
+use glob::Pattern;
+use lazy_static::lazy_static;
+use regex::Regex;
+#[cfg(feature = "serde")]
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader, Read},
+    path::Path,
+    str::FromStr,
+};
+
+/// Various types of owners
+///
+/// Owners supports parsing from strings as well as displaying as strings
+///
+/// # Examples
+///
+/// ```rust
+/// let raw = "@org/team";
+/// assert_eq!(
+///   raw.parse::<codeowners::Owner>().unwrap().to_string(),
+///   raw
+/// );
+/// ```
+///
+/// With `serde` feature owners are serialized with their kind,
+/// e.g. `{"kind":"team","value":"@org/team"}`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(tag = "kind", content = "value", rename_all = "lowercase")
+)]
+pub enum Owner {
+    /// Owner in the form @username
+    Username(String),
+    /// Owner in the form @org/Team
+    Team(String),
+    /// Owner in the form user@domain.com
+    Email(String),
+}
+
+impl fmt::Display for Owner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let inner = match *self {
+            Owner::Username(ref u) => u,
+            Owner::Team(ref t) => t,
+            Owner::Email(ref e) => e,
+        };
+        f.write_str(inner.as_str())
+    }
+}
+
+impl FromStr for Owner {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        lazy_static! {
+            static ref TEAM: Regex = Regex::new(r"^@\S+/\S+").unwrap();
+            static ref USERNAME: Regex = Regex::new(r"^@\S+").unwrap();
+            static ref EMAIL: Regex = Regex::new(r"^\S+@\S+").unwrap();
+        }
+
+        if TEAM.is_match(s) {
+            Ok(Owner::Team(s.into()))
+        } else if USERNAME.is_match(s) {
+            Ok(Owner::Username(s.into()))
+        } else if EMAIL.is_match(s) {
+            Ok(Owner::Email(s.into()))
+        } else {
+            Err(String::from("not an owner"))
+        }
+    }
+}
+
+/// Single line of a CODEOWNERS file mapping a path pattern to owners
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Rule {
+    /// Pattern normalized for matching
+    pub pattern: Pattern,
+    /// Pattern as it is written in the CODEOWNERS file
+    pub raw_pattern: String,
+    /// Owners of matching paths. Empty list means paths have no owners
+    pub owners: Vec<Owner>,
+    /// 1-based line number in the CODEOWNERS file
+    pub line: usize,
+    /// Name of the GitLab section the rule belongs to.
+    /// None for rules above the first section header
+    pub section: Option<String>,
+}
+
+/// Pattern is serialized as it is written in the CODEOWNERS file
+#[cfg(feature = "serde")]
+impl Serialize for Rule {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("Rule", 4)?;
+        state.serialize_field("pattern", &self.raw_pattern)?;
+        state.serialize_field("owners", &self.owners)?;
+        state.serialize_field("line", &self.line)?;
+        state.serialize_field("section", &self.section)?;
+        state.end()
+    }
+}
+
+impl Rule {
+    /// Whether this rule applies to a given path
+    pub fn matches(&self, path: &Path) -> bool {
+        let pattern = &self.pattern;
+        let opts = glob::MatchOptions {
+            case_sensitive: false,
+            require_literal_separator: pattern.as_str().contains('/'),
+            require_literal_leading_dot: false,
+        };
+        if pattern.matches_path_with(path, opts) {
+            true
+        } else {
+            // this pattern is only meant to match
+            // direct children
+            if pattern.as_str().ends_with("/*") {
+                return false;
+            }
+            // case of implied owned children
+            // foo/bar @owner should indicate that foo/bar/baz.rs is
+            // owned by @owner
+            let mut p = path;
+            while let Some(parent) = p.parent() {
+                if pattern.matches_path_with(parent, opts) {
+                    return true;
+                } else {
+                    p = parent;
+                }
+            }
+            false
+        }
+    }
+}
+
+/// GitLab section started by `[Name]` header.
+/// `^[Name]` makes the section optional, `[Name][2]` requires 2 approvals,
+/// owners after the header are used by rules of the section without owners
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Section {
+    /// Name as it is written in the first header of the section
+    pub name: String,
+    /// Approval of optional section is not required
+    pub optional: bool,
+    /// Count of approvals required from owners of the section
+    pub approvals: usize,
+    /// Owners of rules of the section which don't list their own owners
+    pub default_owners: Vec<Owner>,
+    /// 1-based line number of the first header of the section
+    pub line: usize,
+}
+
+/// Problem found on a CODEOWNERS line. The rest of the line is still used
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Diagnostic {
+    /// 1-based line number in the CODEOWNERS file
+    pub line: usize,
+    /// What is wrong with the line
+    pub kind: DiagnosticKind,
+}
+
+/// Kinds of problems reported by the parser
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize),
+    serde(tag = "kind", content = "value", rename_all = "snake_case")
+)]
+pub enum DiagnosticKind {
+    /// Word after the pattern which is neither @username, @org/team nor email
+    InvalidOwner(String),
+    /// Pattern which can't be compiled, the whole line is skipped
+    InvalidPattern(String),
+    /// Line which is not valid UTF-8, the whole line is skipped
+    InvalidEncoding,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            DiagnosticKind::InvalidOwner(ref owner) => {
+                write!(f, "line {}: {} is not an owner", self.line, owner)
+            }
+            DiagnosticKind::InvalidPattern(ref pattern) => {
+                write!(f, "line {}: invalid pattern {}", self.line, pattern)
+            }
+            DiagnosticKind::InvalidEncoding => write!(f, "line {}: invalid UTF-8", self.line),
+        }
+    }
+}
+
+/// Mappings of owners to path patterns
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Owners {
+    rules: Vec<Rule>,
+    sections: Vec<Section>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Owners {
+    /// Resolve a list of owners matching a given path
+    pub fn of<P>(&self, path: P) -> Option<&Vec<Owner>>
+    where
+        P: AsRef<Path>,
+    {
+        self.rule_of(path).map(|rule| &rule.owners)
+    }
+
+    /// Resolve the rule which determines owners of a given path
+    pub fn rule_of<P>(&self, path: P) -> Option<&Rule>
+    where
+        P: AsRef<Path>,
+    {
+        self.rules.iter().find(|rule| rule.matches(path.as_ref()))
+    }
+
+    /// Resolve all rules matching a given path in order of precedence.
+    /// Only the first one determines owners, others are overridden by it
+    pub fn matching_rules<P>(&self, path: P) -> Vec<&Rule>
+    where
+        P: AsRef<Path>,
+    {
+        self.rules
+            .iter()
+            .filter(|rule| rule.matches(path.as_ref()))
+            .collect()
+    }
+
+    /// Rules in order of precedence, the last line of the file goes first
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// GitLab sections in order of lines
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    /// Find a GitLab section by name. Names are case insensitive like in GitLab
+    pub fn section(&self, name: &str) -> Option<&Section> {
+        self.sections
+            .iter()
+            .find(|section| section.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Resolve the rule of every section matching a given path the way GitLab does.
+    /// Rules above the first section header go first, then sections in order of lines
+    pub fn section_rules_of<P>(&self, path: P) -> Vec<&Rule>
+    where
+        P: AsRef<Path>,
+    {
+        let names = std::iter::once(None).chain(
+            self.sections
+                .iter()
+                .map(|section| Some(section.name.as_str())),
+        );
+        names
+            .filter_map(|name| {
+                self.rules
+                    .iter()
+                    .filter(|rule| rule.section.as_deref() == name)
+                    .find(|rule| rule.matches(path.as_ref()))
+            })
+            .collect()
+    }
+
+    /// Problems found while parsing, in order of lines
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+}
+
+/// Parse a CODEOWNERS file existing on a local file system
+///
+/// # Panics
+///
+/// Panics if the file can't be opened
+pub fn from_path<P>(path: P) -> Owners
+where
+    P: AsRef<Path>,
+{
+    from_reader(File::open(path).unwrap())
+}
+
+/// Parse a CODEOWNERS file from some readable source
+/// This format is defined in
+/// [Github's documentation](https://help.github.com/articles/about-codeowners/)
+/// The syntax is uses gitgnore
+/// [patterns](https://www.kernel.org/pub/software/scm/git/docs/gitignore.html#_pattern_format)
+/// followed by an identifier for an owner. More information can be found
+/// [here](https://help.github.com/articles/about-codeowners/#codeowners-syntax)
+pub fn from_reader<R>(read: R) -> Owners
+where
+    R: Read,
+{
+    lazy_static! {
+        static ref SECTION_REGEX: Regex =
+            Regex::new(r"^(\^)?\[([^\]]+)\](?:\[(\d+)\])?(?:\s+(.*))?$").unwrap();
+    }
+    let mut diagnostics = Vec::new();
+    let mut sections: Vec<Section> = Vec::new();
+    // index of the section the following rules belong to
+    let mut current_section: Option<usize> = None;
+    let mut lines = Vec::new();
+    for (index, line) in BufReader::new(read).lines().enumerate() {
+        match line {
+            Ok(line) => lines.push((index, line)),
+            // bytes of the bad line are consumed, so reading goes on with the next one
+            Err(ref error) if error.kind() == io::ErrorKind::InvalidData => {
+                diagnostics.push(Diagnostic {
+                    line: index + 1,
+                    kind: DiagnosticKind::InvalidEncoding,
+                })
+            }
+            // other read errors would repeat on every call
+            Err(_) => break,
+        }
+    }
+    let mut rules = lines
+        .into_iter()
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .fold(Vec::new(), |mut rules, (index, line)| {
+            if let Some(captures) = SECTION_REGEX.captures(line.trim()) {
+                let name = captures[2].trim();
+                current_section = match sections
+                    .iter()
+                    .position(|section| section.name.eq_ignore_ascii_case(name))
+                {
+                    // GitLab merges sections with the same name
+                    Some(position) => Some(position),
+                    None => {
+                        let default_owners = parse_owners(
+                            index + 1,
+                            &mut diagnostics,
+                            &mut captures
+                                .get(4)
+                                .map_or("", |owners| owners.as_str())
+                                .split_whitespace(),
+                        );
+                        sections.push(Section {
+                            name: name.to_string(),
+                            optional: captures.get(1).is_some(),
+                            approvals: captures
+                                .get(3)
+                                .and_then(|approvals| approvals.as_str().parse().ok())
+                                .unwrap_or(1),
+                            default_owners,
+                            line: index + 1,
+                        });
+                        Some(sections.len() - 1)
+                    }
+                };
+                return rules;
+            }
+            let mut elements = split_words(&line).into_iter();
+            if let Some(pattern) = elements.next() {
+                let mut owners = parse_owners(index + 1, &mut diagnostics, &mut elements);
+                let section = current_section.map(|position| &sections[position]);
+                if owners.is_empty() {
+                    if let Some(section) = section {
+                        owners = section.default_owners.clone();
+                    }
+                }
+                match make_pattern(pattern) {
+                    Ok(compiled) => rules.push(Rule {
+                        pattern: compiled,
+                        raw_pattern: pattern.to_string(),
+                        owners,
+                        line: index + 1,
+                        section: section.map(|section| section.name.clone()),
+                    }),
+                    Err(_) => diagnostics.push(Diagnostic {
+                        line: index + 1,
+                        kind: DiagnosticKind::InvalidPattern(pattern.to_string()),
+                    }),
+                }
+            }
+            rules
+        });
+    // last match takes precedence
+    rules.reverse();
+    Owners {
+        rules,
+        sections,
+        diagnostics,
+    }
+}
+
+fn parse_owners(
+    line: usize,
+    diagnostics: &mut Vec<Diagnostic>,
+    elements: &mut dyn Iterator<Item = &str>,
+) -> Vec<Owner> {
+    elements.fold(Vec::new(), |mut result, owner| {
+        match owner.parse() {
+            Ok(owner) => result.push(owner),
+            Err(_) => diagnostics.push(Diagnostic {
+                line,
+                kind: DiagnosticKind::InvalidOwner(owner.to_string()),
+            }),
+        }
+        result
+    })
+}
+
+/// Splits a line by whitespace which is not escaped, so `/my\ docs/` stays one pattern
+fn split_words(line: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut escaped = false;
+    for (index, character) in line.char_indices() {
+        if character.is_whitespace() && !escaped {
+            if let Some(start) = start.take() {
+                words.push(&line[start..index]);
+            }
+        } else if start.is_none() {
+            start = Some(index);
+        }
+        escaped = character == '\\' && !escaped;
+    }
+    if let Some(start) = start {
+        words.push(&line[start..]);
+    }
+    words
+}
+
+fn make_pattern(raw_path: &str) -> Result<Pattern, glob::PatternError> {
+    lazy_static! {
+        static ref ESCAPE_REGEX: Regex = Regex::new(r"\\(\[|\])").unwrap();
+    }
+    // Replaces "\["=>"[[]", "\]"=>"[]]" and "\ "=>" "
+    let path = ESCAPE_REGEX
+        .replace_all(raw_path, "[$1]")
+        .replace("\\ ", " ");
+    // if pattern starts with anchor or explicit wild card, it should
+    // match any prefix
+    let prefixed = if path.starts_with('*') || path.starts_with('/') {
+        path.to_owned()
+    } else {
+        format!("**/{}", path)
+    };
+    // if pattern starts with anchor it should only match paths
+    // relative to root
+    let mut normalized = prefixed.trim_start_matches('/').to_string();
+    // if pattern ends with /, it should match children of that directory
+    if normalized.ends_with('/') {
+        normalized.push_str("**");
+    }
+    Pattern::new(&normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const EXAMPLE: &str = r"# This is a comment.
+# Each line is a file pattern followed by one or more owners.
+
+# These owners will be the default owners for everything in
+# the repo. Unless a later match takes precedence,
+# @global-owner1 and @global-owner2 will be requested for
+# review when someone opens a pull request.
+*       @global-owner1 @global-owner2
+
+# Order is important; the last matching pattern takes the most
+# precedence. When someone opens a pull request that only
+# modifies JS files, only @js-owner and not the global
+# owner(s) will be requested for a review.
+*.js    @js-owner
+
+# You can also use email addresses if you prefer. They'll be
+# used to look up users just like we do for commit author
+# emails.
+*.go docs@example.com
+
+# In this example, @doctocat owns any files in the build/logs
+# directory at the root of the repository and any of its
+# subdirectories.
+/build/logs/ @doctocat
+
+# The `docs/*` pattern will match files like
+# `docs/getting-started.md` but not further nested files like
+# `docs/build-app/troubleshooting.md`.
+docs/*  docs@example.com
+
+# In this example, @octocat owns any file in an apps directory
+# anywhere in your repository.
+apps/ @octocat
+
+# In this example, @doctocat owns any file in the `/docs`
+# directory in the root of your repository.
+/docs/ @doctocat
+";
+
+    #[test]
+    fn owner_parses() {
+        assert!("@user".parse() == Ok(Owner::Username("@user".into())));
+        assert!("@org/team".parse() == Ok(Owner::Team("@org/team".into())));
+        assert!("user@domain.com".parse() == Ok(Owner::Email("user@domain.com".into())));
+        assert!("bogus".parse::<Owner>() == Err("not an owner".into()));
+    }
+
+    #[test]
+    fn owner_displays() {
+        assert!(Owner::Username("@user".into()).to_string() == "@user");
+        assert!(Owner::Team("@org/team".into()).to_string() == "@org/team");
+        assert!(Owner::Email("user@domain.com".into()).to_string() == "user@domain.com");
+    }
+
+    #[test]
+    fn from_reader_parses() {
+        let owners = from_reader(EXAMPLE.as_bytes());
+        assert_eq!(
+            owners,
+            Owners {
+                rules: vec![
+                    Rule {
+                        pattern: Pattern::new("docs/**").unwrap(),
+                        raw_pattern: "/docs/".into(),
+                        owners: vec![Owner::Username("@doctocat".into())],
+                        line: 37,
+                        section: None,
+                    },
+                    Rule {
+                        pattern: Pattern::new("**/apps/**").unwrap(),
+                        raw_pattern: "apps/".into(),
+                        owners: vec![Owner::Username("@octocat".into())],
+                        line: 33,
+                        section: None,
+                    },
+                    Rule {
+                        pattern: Pattern::new("**/docs/*").unwrap(),
+                        raw_pattern: "docs/*".into(),
+                        owners: vec![Owner::Email("docs@example.com".into())],
+                        line: 29,
+                        section: None,
+                    },
+                    Rule {
+                        pattern: Pattern::new("build/logs/**").unwrap(),
+                        raw_pattern: "/build/logs/".into(),
+                        owners: vec![Owner::Username("@doctocat".into())],
+                        line: 24,
+                        section: None,
+                    },
+                    Rule {
+                        pattern: Pattern::new("*.go").unwrap(),
+                        raw_pattern: "*.go".into(),
+                        owners: vec![Owner::Email("docs@example.com".into())],
+                        line: 19,
+                        section: None,
+                    },
+                    Rule {
+                        pattern: Pattern::new("*.js").unwrap(),
+                        raw_pattern: "*.js".into(),
+                        owners: vec![Owner::Username("@js-owner".into())],
+                        line: 14,
+                        section: None,
+                    },
+                    Rule {
+                        pattern: Pattern::new("*").unwrap(),
+                        raw_pattern: "*".into(),
+                        owners: vec![
+                            Owner::Username("@global-owner1".into()),
+                            Owner::Username("@global-owner2".into()),
+                        ],
+                        line: 8,
+                        section: None,
+                    },
+                ],
+                sections: vec![],
+                diagnostics: vec![],
+            }
+        )
+    }
+
+    #[test]
+    fn owners_owns_wildcard() {
+        let owners = from_reader(EXAMPLE.as_bytes());
+        assert_eq!(
+            owners.of("foo.txt"),
+            Some(&vec![
+                Owner::Username("@global-owner1".into()),
+                Owner::Username("@global-owner2".into()),
+            ])
+        );
+        assert_eq!(
+            owners.of("foo/bar.txt"),
+            Some(&vec![
+                Owner::Username("@global-owner1".into()),
+                Owner::Username("@global-owner2".into()),
+            ])
+        )
+    }
+
+    #[test]
+    fn owners_owns_js_extention() {
+        let owners = from_reader(EXAMPLE.as_bytes());
+        assert_eq!(
+            owners.of("foo.js"),
+            Some(&vec![Owner::Username("@js-owner".into())])
+        );
+        assert_eq!(
+            owners.of("foo/bar.js"),
+            Some(&vec![Owner::Username("@js-owner".into())])
+        )
+    }
+
+    #[test]
+    fn owners_owns_go_extention() {
+        let owners = from_reader(EXAMPLE.as_bytes());
+        assert_eq!(
+            owners.of("foo.go"),
+            Some(&vec![Owner::Email("docs@example.com".into())])
+        );
+        assert_eq!(
+            owners.of("foo/bar.go"),
+            Some(&vec![Owner::Email("docs@example.com".into())])
+        )
+    }
+
+    #[test]
+    fn owners_owns_anchored_build_logs() {
+        let owners = from_reader(EXAMPLE.as_bytes());
+        // relative to root
+        assert_eq!(
+            owners.of("build/logs/foo.go"),
+            Some(&vec![Owner::Username("@doctocat".into())])
+        );
+        assert_eq!(
+            owners.of("build/logs/foo/bar.go"),
+            Some(&vec![Owner::Username("@doctocat".into())])
+        );
+        // not relative to root
+        assert_eq!(
+            owners.of("foo/build/logs/foo.go"),
+            Some(&vec![Owner::Email("docs@example.com".into())])
+        )
+    }
+
+    #[test]
+    fn owners_owns_unanchored_docs() {
+        let owners = from_reader(EXAMPLE.as_bytes());
+        // docs anywhere
+        assert_eq!(
+            owners.of("foo/docs/foo.js"),
+            Some(&vec![Owner::Email("docs@example.com".into())])
+        );
+        assert_eq!(
+            owners.of("foo/bar/docs/foo.js"),
+            Some(&vec![Owner::Email("docs@example.com".into())])
+        );
+        // but not nested
+        assert_eq!(
+            owners.of("foo/bar/docs/foo/foo.js"),
+            Some(&vec![Owner::Username("@js-owner".into())])
+        )
+    }
+
+    #[test]
+    fn owners_owns_unanchored_apps() {
+        let owners = from_reader(EXAMPLE.as_bytes());
+        assert_eq!(
+            owners.of("foo/apps/foo.js"),
+            Some(&vec![Owner::Username("@octocat".into())])
+        )
+    }
+
+    #[test]
+    fn owners_owns_anchored_docs() {
+        let owners = from_reader(EXAMPLE.as_bytes());
+        // relative to root
+        assert_eq!(
+            owners.of("docs/foo.js"),
+            Some(&vec![Owner::Username("@doctocat".into())])
+        )
+    }
+
+    #[test]
+    fn matching_rules_in_precedence_order() {
+        let owners = from_reader(EXAMPLE.as_bytes());
+        assert_eq!(
+            owners
+                .matching_rules("docs/foo.js")
+                .iter()
+                .map(|rule| rule.line)
+                .collect::<Vec<usize>>(),
+            vec![37, 29, 14, 8]
+        )
+    }
+
+    #[test]
+    fn parses_gitlab_sections() {
+        let owners = from_reader(
+            "* @org/core\n^[Docs][2] @org/docs\n*.md\n[Backend]\n*.rs @bob\n[docs]\n/guide/ @alice\n"
+                .as_bytes(),
+        );
+        assert_eq!(
+            owners.sections(),
+            &[
+                Section {
+                    name: "Docs".into(),
+                    optional: true,
+                    approvals: 2,
+                    default_owners: vec![Owner::Team("@org/docs".into())],
+                    line: 2,
+                },
+                Section {
+                    name: "Backend".into(),
+                    optional: false,
+                    approvals: 1,
+                    default_owners: vec![],
+                    line: 4,
+                },
+            ]
+        );
+        assert_eq!(
+            owners
+                .section_rules_of("guide/intro.md")
+                .iter()
+                .map(|rule| (rule.line, rule.section.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![(1, None), (7, Some("Docs"))]
+        );
+        // GitHub resolution ignores sections, the last matching line wins
+        assert_eq!(owners.rule_of("guide/intro.md").unwrap().line, 7);
+    }
+
+    #[test]
+    fn reports_diagnostics() {
+        let owners = from_reader("*.rs @rust bogus\n\nsrc/a**b @stars\n".as_bytes());
+        assert_eq!(
+            owners.diagnostics(),
+            &[
+                Diagnostic {
+                    line: 1,
+                    kind: DiagnosticKind::InvalidOwner("bogus".into()),
+                },
+                Diagnostic {
+                    line: 3,
+                    kind: DiagnosticKind::InvalidPattern("src/a**b".into()),
+                },
+            ]
+        );
+        assert_eq!(owners.rules().len(), 1);
+        assert_eq!(
+            owners.of("main.rs"),
+            Some(&vec![Owner::Username("@rust".into())])
+        )
+    }
+
+    #[test]
+    fn skips_lines_with_invalid_utf8() {
+        let owners = from_reader(&b"*.rs @rust\n/bad\xff/ @bad\n/docs/ @docs\n"[..]);
+        assert_eq!(
+            owners.diagnostics(),
+            &[Diagnostic {
+                line: 2,
+                kind: DiagnosticKind::InvalidEncoding,
+            }]
+        );
+        assert_eq!(
+            owners
+                .rules()
+                .iter()
+                .map(|rule| rule.line)
+                .collect::<Vec<_>>(),
+            vec![3, 1]
+        );
+        assert_eq!(
+            owners.of("docs/a.md"),
+            Some(&vec![Owner::Username("@docs".into())])
+        )
+    }
+
+    #[test]
+    fn owners_sort_by_kind_then_name() {
+        let mut owners = vec![
+            Owner::Email("docs@example.com".into()),
+            Owner::Team("@org/b".into()),
+            Owner::Username("@zed".into()),
+            Owner::Team("@org/a".into()),
+        ];
+        owners.sort();
+        assert_eq!(
+            owners,
+            vec![
+                Owner::Username("@zed".into()),
+                Owner::Team("@org/a".into()),
+                Owner::Team("@org/b".into()),
+                Owner::Email("docs@example.com".into()),
+            ]
+        )
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn owner_serializes_with_kind() {
+        let owner = Owner::Team("@org/team".into());
+        let json = serde_json::to_string(&owner).unwrap();
+        assert_eq!(json, r#"{"kind":"team","value":"@org/team"}"#);
+        assert_eq!(serde_json::from_str::<Owner>(&json).unwrap(), owner);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn owners_serialize_rules_and_diagnostics() {
+        let owners = from_reader("/docs/ @org/docs bogus\n".as_bytes());
+        assert_eq!(
+            serde_json::to_string(&owners).unwrap(),
+            r#"{"rules":[{"pattern":"/docs/","owners":[{"kind":"team","value":"@org/docs"}],"line":1,"section":null}],"sections":[],"diagnostics":[{"line":1,"kind":{"kind":"invalid_owner","value":"bogus"}}]}"#
+        )
+    }
+
+    #[test]
+    fn implied_children_owners() {
+        let owners = from_reader("foo/bar @doug".as_bytes());
+        assert_eq!(
+            owners.of("foo/bar/baz.rs"),
+            Some(&vec![Owner::Username("@doug".into())])
+        )
+    }
+
+    #[test]
+    fn escaped_spaces_are_part_of_pattern() {
+        let owners = from_reader("/my\\ docs/ @docs\n/a\\ b.md  @ab @other\n".as_bytes());
+        assert!(owners.diagnostics().is_empty());
+        assert_eq!(
+            owners.of("my docs/intro.md"),
+            Some(&vec![Owner::Username("@docs".into())])
+        );
+        assert_eq!(owners.of("my/intro.md"), None);
+        let rule = owners.rule_of("a b.md").unwrap();
+        assert_eq!(rule.raw_pattern, "/a\\ b.md");
+        assert_eq!(rule.owners.len(), 2);
+    }
+
+    #[test]
+    fn make_pattern_escapes() {
+        let pattern = make_pattern(
+            r"/client/apps/dashboard/pages/dashboard/destinations/\[groupId\]/data-security.page.tsx",
+        )
+        .unwrap();
+
+        assert_eq!(
+            pattern.to_string(),
+            r"client/apps/dashboard/pages/dashboard/destinations/[[]groupId[]]/data-security.page.tsx"
+        )
+    }
+}
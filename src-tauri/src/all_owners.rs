@@ -0,0 +1,187 @@
+//! Owners of all files of a branch in tree format
+use serde::ser::{SerializeStruct, Serializer};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::git::{get_branch_files_vector, get_codeowners_content};
+use crate::{join_owners, normalize_owners};
+use codeowners::Owner;
+
+/**
+ * Returns owners of every file of the branch in tree format.
+ * `on_progress` is called with count of handled files and total count of files.
+ */
+pub fn get_all_codeowners_for_branch_struct<F>(
+    abs_repo_path: &str,
+    branch: &str,
+    mut on_progress: F,
+) -> DirectoryOwners
+where
+    F: FnMut(usize, usize),
+{
+    let codeowners_content = get_codeowners_content(abs_repo_path, branch);
+    let codeowners = codeowners::from_reader(codeowners_content.as_bytes());
+    let files = get_branch_files_vector(abs_repo_path, branch);
+    let mut result: DirectoryOwners = DirectoryOwners {
+        name: String::from(""),
+        directories: vec![],
+        files: vec![],
+        owner: Option::None,
+    };
+
+    for (file_index, file_path) in files.iter().enumerate() {
+        if file_index % 100 == 0 {
+            on_progress(file_index, files.len());
+        }
+        // if file_index > 2000 {
+        //     break; // TODO: we need speedup algorithm. Now it is too long. Uncomment for quick debugging.
+        // }
+        let owners = normalize_owners(codeowners.of(file_path));
+        let owner = join_owners(&owners);
+        let mut current = &mut result;
+        let mut it = file_path.split('/').peekable();
+        while let Some(file_path_part) = it.next() {
+            let is_last_part = it.peek().is_none();
+            if is_last_part {
+                current.files.push(FileOwners {
+                    name: file_path_part.to_string(),
+                    owner: String::from(&owner),
+                    owners: owners.clone(),
+                })
+            } else {
+                if let Some(existing_dir_position) = current
+                    .directories
+                    .iter()
+                    .position(|dir| dir.name == file_path_part)
+                {
+                    current = &mut current.directories[existing_dir_position];
+                } else {
+                    let new_dir_owners = DirectoryOwners {
+                        name: String::from(file_path_part),
+                        directories: vec![],
+                        files: vec![],
+                        owner: Option::None,
+                    };
+                    current.directories.push(new_dir_owners);
+                    current = current
+                        .directories
+                        .iter_mut()
+                        .find(|dir| dir.name == file_path_part)
+                        .expect("Just added this directory, so it should be found")
+                }
+            }
+        }
+    }
+    result
+}
+
+pub struct FileOwners {
+    pub name: String,
+    /** Owners joined with ", ". Empty for unowned files */
+    pub owner: String,
+    /** Owners sorted by kind and name */
+    pub owners: Vec<Owner>,
+}
+
+impl Serialize for FileOwners {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        // 3 is the number of fields in the struct.
+        let mut state = serializer.serialize_struct("FileOwners", 3)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("owner", &self.owner)?;
+        state.serialize_field("owners", &self.owners)?;
+        state.end()
+    }
+}
+
+pub struct DirectoryOwners {
+    /** Directory name. For root folder it is empty */
+    pub name: String,
+    pub directories: Vec<DirectoryOwners>,
+    pub files: Vec<FileOwners>,
+    /**
+     * @return string which contains all owners in case every files/directories inside have their own owners.
+     *   null in other case (for root directory also null)
+     */
+    pub owner: Option<String>,
+}
+
+impl Serialize for DirectoryOwners {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        // 4 is the number of fields in the struct.
+        let mut state = serializer.serialize_struct("DirectoryOwners", 4)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("directories", &self.directories)?;
+        state.serialize_field("files", &self.files)?;
+        state.serialize_field("owner", &self.owner)?;
+        state.end()
+    }
+}
+
+impl DirectoryOwners {
+    /** Owners shared by all files inside of the directory. None if files have different owners */
+    pub fn common_owner(&self) -> Option<&str> {
+        let mut owners = self
+            .files
+            .iter()
+            .map(|file| Some(file.owner.as_str()))
+            .chain(self.directories.iter().map(|dir| dir.common_owner()));
+        let first = owners.next()??;
+        if owners.all(|owner| owner == Some(first)) {
+            Some(first)
+        } else {
+            None
+        }
+    }
+
+    /** Owners of every file inside of the directory by path relative to it */
+    pub fn owners_by_path(&self) -> HashMap<String, &Vec<Owner>> {
+        let mut owners_by_path = HashMap::new();
+        self.collect_owners("", &mut owners_by_path);
+        owners_by_path
+    }
+
+    fn collect_owners<'a>(
+        &'a self,
+        path: &str,
+        owners_by_path: &mut HashMap<String, &'a Vec<Owner>>,
+    ) {
+        for file in &self.files {
+            owners_by_path.insert(format!("{path}{}", file.name), &file.owners);
+        }
+        for directory in &self.directories {
+            directory.collect_owners(&format!("{path}{}/", directory.name), owners_by_path);
+        }
+    }
+
+    /** Paths of directories inside of the directory down to `max_depth`, parents before children */
+    pub fn directory_paths(&self, max_depth: usize) -> Vec<String> {
+        let mut paths = Vec::new();
+        self.collect_directories("", max_depth, &mut paths);
+        paths
+    }
+
+    fn collect_directories(&self, path: &str, max_depth: usize, paths: &mut Vec<String>) {
+        if max_depth == 0 {
+            return;
+        }
+        for directory in &self.directories {
+            let directory_path = format!("{path}{}", directory.name);
+            paths.push(directory_path.clone());
+            directory.collect_directories(&format!("{directory_path}/"), max_depth - 1, paths);
+        }
+    }
+}
+
+/** Directories containing the file path down to `max_depth`, e.g. `src` and `src/app` for `src/app/main.rs` */
+pub fn parent_directories(path: &str, max_depth: usize) -> impl Iterator<Item = &str> {
+    path.match_indices('/')
+        .take(max_depth)
+        .map(move |(position, _)| &path[..position])
+}
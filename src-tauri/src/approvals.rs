@@ -0,0 +1,300 @@
+//! Which changed files of a branch still lack approval of their owners given people and teams who approved
+use serde::Serialize;
+
+use crate::branch_diff::{
+    self, BranchCodeownersFiles, ChangeStatus, ChangedFile, CodeownersRevision,
+};
+use crate::git;
+use crate::identity::IdentityResolver;
+use crate::normalize_owners;
+use crate::roster::Roster;
+use codeowners::{Owner, Owners};
+
+/** Trailer of commit messages which lists people who approved the branch */
+pub const APPROVED_BY_TRAILER: &str = "Approved-by";
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct BlockedFile {
+    pub path: String,
+    pub owners: Vec<Owner>,
+    /** GitLab section which still needs approvals. Null in GitHub mode and for rules above the first section */
+    pub section: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SectionApprovals {
+    /** Null for rules above the first section header */
+    pub section: Option<String>,
+    pub optional: bool,
+    pub required: usize,
+    /** Approvers who own at least one changed file of the section */
+    pub approved_by: Vec<String>,
+    /** Approvals still needed by the least approved file of the section. Always 0 for optional sections */
+    pub missing: usize,
+    pub files: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ApprovalReport {
+    pub approvers: Vec<String>,
+    pub blocked_files: Vec<BlockedFile>,
+    /** Null unless GitLab sections are requested */
+    pub sections: Option<Vec<SectionApprovals>>,
+}
+
+impl ApprovalReport {
+    pub fn approved(&self) -> bool {
+        self.blocked_files.is_empty()
+    }
+}
+
+/**
+ * Simulates approvals of changed files of the branch.
+ * Without passed approvers they are read from `Approved-by` trailers of branch commits.
+ * With GitLab sections every section matching a file needs its own approvals,
+ * otherwise any one owner of the file is enough like on GitHub.
+ */
+pub fn simulate_branch_approvals(
+    abs_repo_path: &str,
+    branch: &str,
+    revision: CodeownersRevision,
+    roster: &Roster,
+    approvers: Option<Vec<String>>,
+    gitlab_sections: bool,
+) -> Result<ApprovalReport, String> {
+    let approvers = approvers.unwrap_or_else(|| {
+        let range = format!("{}..{branch}", git::BASE_BRANCH);
+        let trailers = git::get_trailers(abs_repo_path, &range, APPROVED_BY_TRAILER);
+        let identities = IdentityResolver::load(abs_repo_path, roster.clone());
+        approvers_from_trailers(&trailers, &identities)
+    });
+    let codeowners = BranchCodeownersFiles::load(abs_repo_path, branch)?;
+    let changes = branch_diff::get_branch_changes(abs_repo_path, branch)?;
+    let paths = changed_paths(&changes, &codeowners, revision);
+    Ok(simulate_approvals(
+        &paths,
+        &approvers,
+        roster,
+        gitlab_sections,
+    ))
+}
+
+/**
+ * Pairs of changed path and CODEOWNERS it is resolved with, the same way as changes are grouped.
+ * Deleted files and old paths of renamed files are resolved with the base.
+ */
+pub fn changed_paths<'a>(
+    changes: &[ChangedFile],
+    codeowners: &'a BranchCodeownersFiles,
+    revision: CodeownersRevision,
+) -> Vec<(String, &'a Owners)> {
+    let revisions: Vec<&Owners> = match revision {
+        CodeownersRevision::Base => vec![&codeowners.base],
+        CodeownersRevision::Head => vec![&codeowners.head],
+        CodeownersRevision::Both => vec![&codeowners.base, &codeowners.head],
+    };
+    let mut result: Vec<(String, &Owners)> = Vec::new();
+    for change in changes {
+        match change.status {
+            ChangeStatus::Deleted => result.push((change.path.clone(), &codeowners.base)),
+            _ => result.extend(
+                revisions
+                    .iter()
+                    .map(|owners| (change.path.clone(), *owners)),
+            ),
+        }
+        if let (ChangeStatus::Renamed, Some(old_path)) = (change.status, &change.old_path) {
+            result.push((old_path.clone(), &codeowners.base));
+        }
+    }
+    result
+}
+
+/** Approvers of `Approved-by` trailers: usernames and teams as they are, `Name <email>` mapped to usernames */
+pub fn approvers_from_trailers(values: &[String], identities: &IdentityResolver) -> Vec<String> {
+    let mut approvers: Vec<String> = values
+        .iter()
+        .map(|value| match (value.find('<'), value.rfind('>')) {
+            (Some(start), Some(end)) if start < end => {
+                let identity = identities.resolve(value[..start].trim(), &value[start + 1..end]);
+                identity.username.unwrap_or(identity.email)
+            }
+            _ => value.trim().to_string(),
+        })
+        .collect();
+    approvers.sort();
+    approvers.dedup();
+    approvers
+}
+
+fn approvers_of<'a>(roster: &Roster, approvers: &'a [String], owners: &[Owner]) -> Vec<&'a String> {
+    approvers
+        .iter()
+        .filter(|approver| owners.iter().any(|owner| roster.is_owner(owner, approver)))
+        .collect()
+}
+
+pub fn simulate_approvals(
+    paths: &[(String, &Owners)],
+    approvers: &[String],
+    roster: &Roster,
+    gitlab_sections: bool,
+) -> ApprovalReport {
+    let mut blocked_files: Vec<BlockedFile> = Vec::new();
+    let mut sections: Vec<SectionApprovals> = Vec::new();
+    for (path, codeowners) in paths {
+        if !gitlab_sections {
+            let owners = normalize_owners(codeowners.of(path));
+            if !owners.is_empty() && approvers_of(roster, approvers, &owners).is_empty() {
+                blocked_files.push(BlockedFile {
+                    path: path.clone(),
+                    owners,
+                    section: None,
+                });
+            }
+            continue;
+        }
+        for rule in codeowners.section_rules_of(path) {
+            let owners = normalize_owners(Some(&rule.owners));
+            if owners.is_empty() {
+                continue;
+            }
+            let section = rule
+                .section
+                .as_ref()
+                .and_then(|name| codeowners.section(name));
+            let optional = matches!(section, Some(section) if section.optional);
+            let required = section.map_or(1, |section| section.approvals);
+            let approved_by = approvers_of(roster, approvers, &owners);
+            let missing = if optional {
+                0
+            } else {
+                required.saturating_sub(approved_by.len())
+            };
+            if missing > 0 {
+                blocked_files.push(BlockedFile {
+                    path: path.clone(),
+                    owners: owners.clone(),
+                    section: rule.section.clone(),
+                });
+            }
+            let summary = match sections
+                .iter()
+                .position(|summary| summary.section == rule.section)
+            {
+                Some(position) => &mut sections[position],
+                None => {
+                    sections.push(SectionApprovals {
+                        section: rule.section.clone(),
+                        optional,
+                        required,
+                        approved_by: vec![],
+                        missing: 0,
+                        files: vec![],
+                    });
+                    sections.last_mut().unwrap()
+                }
+            };
+            summary.approved_by.extend(approved_by.into_iter().cloned());
+            summary.missing = summary.missing.max(missing);
+            summary.files.push(path.clone());
+        }
+    }
+    for summary in sections.iter_mut() {
+        summary.approved_by.sort();
+        summary.approved_by.dedup();
+        summary.files.sort();
+        summary.files.dedup();
+    }
+    blocked_files.sort_by(|a, b| (&a.path, &a.section).cmp(&(&b.path, &b.section)));
+    blocked_files.dedup();
+    ApprovalReport {
+        approvers: approvers.to_vec(),
+        blocked_files,
+        sections: if gitlab_sections {
+            Some(sections)
+        } else {
+            None
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::parse_mailmap;
+
+    const CODEOWNERS: &str = r"* @org/core
+/payments/ @org/payments
+[Security][2] @alice @bob @carol
+/payments/
+^[Docs] @dave
+*.md
+";
+
+    fn roster() -> Roster {
+        serde_json::from_str(
+            r#"{ "teams": { "@org/payments": ["@alice"] }, "aliases": { "@bob": ["bob@example.com"] } }"#,
+        )
+        .unwrap()
+    }
+
+    fn paths(codeowners: &Owners) -> Vec<(String, &Owners)> {
+        vec![
+            (String::from("payments/api.rs"), codeowners),
+            (String::from("README.md"), codeowners),
+        ]
+    }
+
+    #[test]
+    fn github_mode_needs_any_owner() {
+        let codeowners = codeowners::from_reader(CODEOWNERS.as_bytes());
+        let report = simulate_approvals(&paths(&codeowners), &["@alice".into()], &roster(), false);
+        assert_eq!(report.sections, None);
+        assert_eq!(
+            report.blocked_files,
+            vec![BlockedFile {
+                path: "README.md".into(),
+                owners: vec![Owner::Username("@dave".into())],
+                section: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn gitlab_mode_counts_approvals_per_section() {
+        let codeowners = codeowners::from_reader(CODEOWNERS.as_bytes());
+        let approvers = vec![String::from("@alice")];
+        let report = simulate_approvals(&paths(&codeowners), &approvers, &roster(), true);
+        assert_eq!(
+            report
+                .blocked_files
+                .iter()
+                .map(|file| (file.path.as_str(), file.section.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![("README.md", None), ("payments/api.rs", Some("Security"))]
+        );
+        let sections = report.sections.unwrap();
+        assert_eq!(sections.len(), 3);
+        assert_eq!(sections[1].section.as_deref(), Some("Security"));
+        assert_eq!(sections[1].approved_by, vec!["@alice"]);
+        assert_eq!(sections[1].missing, 1);
+        assert!(sections[2].optional);
+        assert_eq!(sections[2].missing, 0);
+    }
+
+    #[test]
+    fn reads_approvers_from_trailers() {
+        let identities =
+            IdentityResolver::new(parse_mailmap("<bob@example.com> <bob@laptop>\n"), roster());
+        let trailers = vec![
+            String::from("Bob <bob@laptop>"),
+            String::from("@org/payments"),
+            String::from("Eve <eve@example.com>"),
+        ];
+        assert_eq!(
+            approvers_from_trailers(&trailers, &identities),
+            vec!["@bob", "@org/payments", "eve@example.com"]
+        );
+    }
+}
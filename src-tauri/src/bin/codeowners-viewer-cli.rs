@@ -0,0 +1,991 @@
+//! Command line interface to the same backend which is used by the desktop app
+use serde_json::json;
+use std::io::{self, BufWriter, Write};
+use std::str::FromStr;
+use std::{env, process};
+
+use app_lib::all_owners::{self, DirectoryOwners};
+use app_lib::approvals;
+use app_lib::branch_check::{self, CheckOptions, Violation};
+use app_lib::branch_diff::{
+    self, ChangeStatus, CodeownersRevision, FrontendCodeowner, OwnersGrouping,
+};
+use app_lib::export::{self, ExportFilter, ExportFormat};
+use app_lib::ownership_history;
+use app_lib::roster::{self, Roster};
+use app_lib::statistics::{self, Totals};
+use app_lib::{churn, coverage, coverage_trend, drift, knowledge, owner_suggestions};
+use app_lib::{git, join_owners, normalize_owners, personal, pr_summary, reviewers};
+use codeowners::{Owners, Rule};
+
+const USAGE: &str = "Usage: codeowners-viewer-cli <command> <argument> [options]
+
+Commands:
+  owners <path>        Show owners of a file
+  explain <path>       Show CODEOWNERS rules matching a file
+  diff <branch>        Show owners of files changed in a branch comparing with origin/main
+  tree <branch>        Show owners of all files of a branch
+  check <branch>       Exit with code 1 if files changed in a branch break ownership conditions
+  reviewers <branch>   Show the smallest set of owners covering every file changed in a branch
+  review <branch>      Show files changed in a branch which need review of --me directly or through teams
+  owned <username>     Show files owned by a user directly or through teams
+  summary <branch>     Print Markdown summary of files changed in a branch for pull request description
+  export <branch>      Export owners of all files of a branch
+  export-diff <branch> Export owners of files changed in a branch
+  stats <branch>       Show files, bytes and lines of a branch per owner and per extension
+  coverage <branch>    Print Markdown report of owned files, bytes and lines per directory of a branch
+  trend <range>        Show coverage of commits of a range like main~200..main sampled once per interval
+  suggest <branch>     Print CODEOWNERS lines for unowned paths from recent authors and owners of neighbours
+  drift <branch>       Show CODEOWNERS rules whose owners don't change their files while others do
+  knowledge <branch>   Show contributors and bus factor per owner and per directory from recent history
+  churn <branch>       Show commits and changed lines per owner group and per directory from recent history
+  history <path>       Show since when and by which rule a file had its owners across CODEOWNERS changes
+  approvals <branch>   Exit with code 1 if files changed in a branch still lack approval of their owners
+  validate <branch>    Exit with code 1 if CODEOWNERS of a branch has invalid lines or owners missing in the roster
+
+Options:
+  --repo <path>        Repository path, current directory by default
+  --branch <branch>    Branch used by owners, explain, owned and history commands, HEAD by default
+  --codeowners <rev>   CODEOWNERS used by diff, export-diff, reviewers, review, summary and approvals commands: base (merge-base with origin/main), head (default) or both
+  --group-by <mode>    Groups of diff command: set (default) of owners or individual owner
+  --allow-unowned      Don't fail check command on changed files without owners
+  --require-team       Fail check command on changed files owned only by users or emails
+  --max-owners <n>     Fail check command on changed files with more owners than n
+  --roster <path>      JSON or YAML roster of known users, teams and emails, relative to the repository
+  --me <username>      Identity used by review command
+  --approver <name>    Username, team or email who approved, can be repeated.
+                       Approved-by trailers of branch commits are used by default
+  --template <path>    Template of summary command, relative to the repository
+  --format <format>    Format of export commands: csv (default), json, markdown or html
+  --output <path>      File written by export commands, standard output by default
+  --depth <n>          Depth of directories listed by coverage, knowledge and churn commands, 2 by default
+  --top <n>            Count of the largest unowned subtrees listed by coverage command, 10 by default
+  --interval <days>    Sampling interval of trend command, 7 days by default
+  --months <n>         History window of suggest, knowledge (12 months by default), drift (6) and churn (3) commands
+  --max-authors <n>    Authors added to each line by suggest command, 2 by default
+  --gitlab             Approvals command requires approvals of every GitLab section
+  --json               Print JSON instead of human-readable output
+  -h, --help           Print this help
+";
+
+struct Options {
+    command: String,
+    argument: String,
+    repo: String,
+    branch: String,
+    codeowners_revision: CodeownersRevision,
+    grouping: OwnersGrouping,
+    check: CheckOptions,
+    roster: Option<String>,
+    me: Option<String>,
+    approvers: Option<Vec<String>>,
+    gitlab_sections: bool,
+    template: Option<String>,
+    format: ExportFormat,
+    output: Option<String>,
+    depth: usize,
+    top: usize,
+    interval_days: u32,
+    months: Option<u32>,
+    max_authors: usize,
+    json: bool,
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1).collect()) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{error}\n\n{USAGE}");
+            process::exit(2);
+        }
+    };
+    match options.command.as_str() {
+        "owners" => owners(&options),
+        "explain" => explain(&options),
+        "diff" => diff(&options),
+        "tree" => tree(&options),
+        "check" => check(&options),
+        "reviewers" => minimal_reviewers(&options),
+        "review" => review(&options),
+        "owned" => owned(&options),
+        "summary" => summary(&options),
+        "export" | "export-diff" => export(&options),
+        "stats" => stats(&options),
+        "coverage" => print_coverage(&options),
+        "trend" => trend(&options),
+        "suggest" => suggest(&options),
+        "drift" => print_drift(&options),
+        "knowledge" => print_knowledge(&options),
+        "churn" => print_churn(&options),
+        "history" => history(&options),
+        "approvals" => simulate_approvals(&options),
+        "validate" => validate(&options),
+        command => {
+            eprintln!("Unknown command {command}\n\n{USAGE}");
+            process::exit(2);
+        }
+    }
+}
+
+fn parse_args(args: Vec<String>) -> Result<Options, String> {
+    let mut positional: Vec<String> = Vec::new();
+    let mut options = Options {
+        command: String::new(),
+        argument: String::new(),
+        repo: String::from("."),
+        branch: String::from("HEAD"),
+        codeowners_revision: CodeownersRevision::default(),
+        grouping: OwnersGrouping::default(),
+        check: CheckOptions::default(),
+        roster: None,
+        me: None,
+        approvers: None,
+        gitlab_sections: false,
+        template: None,
+        format: ExportFormat::Csv,
+        output: None,
+        depth: coverage::DEFAULT_DEPTH,
+        top: coverage::DEFAULT_TOP,
+        interval_days: coverage_trend::DEFAULT_INTERVAL_DAYS,
+        months: None,
+        max_authors: owner_suggestions::DEFAULT_MAX_AUTHORS,
+        json: false,
+    };
+    let mut it = args.into_iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                print!("{USAGE}");
+                process::exit(0);
+            }
+            "--json" => options.json = true,
+            "--allow-unowned" => options.check.fail_on_unowned = false,
+            "--require-team" => options.check.require_team = true,
+            "--gitlab" => options.gitlab_sections = true,
+            "--repo" | "--branch" | "--codeowners" | "--group-by" | "--roster" | "--me"
+            | "--approver" | "--template" | "--format" | "--output" | "--depth" | "--top"
+            | "--interval" | "--months" | "--max-authors" | "--max-owners" => {
+                let value = it.next().ok_or(format!("Missing value of {arg}"))?;
+                match arg.as_str() {
+                    "--repo" => options.repo = value,
+                    "--branch" => options.branch = value,
+                    "--codeowners" => options.codeowners_revision = value.parse()?,
+                    "--group-by" => options.grouping = value.parse()?,
+                    "--roster" => options.roster = Some(value),
+                    "--me" => options.me = Some(value),
+                    "--template" => options.template = Some(value),
+                    "--format" => options.format = value.parse()?,
+                    "--output" => options.output = Some(value),
+                    "--approver" => options.approvers.get_or_insert_with(Vec::new).push(value),
+                    "--depth" => options.depth = parse_number(&arg, &value)?,
+                    "--top" => options.top = parse_number(&arg, &value)?,
+                    "--interval" => options.interval_days = parse_number(&arg, &value)?,
+                    "--months" => options.months = Some(parse_number(&arg, &value)?),
+                    "--max-authors" => options.max_authors = parse_number(&arg, &value)?,
+                    _ => options.check.max_owners = Some(parse_number(&arg, &value)?),
+                }
+            }
+            _ if arg.starts_with('-') => return Err(format!("Unknown option {arg}")),
+            _ => positional.push(arg),
+        }
+    }
+    let mut positional = positional.into_iter();
+    options.command = positional.next().ok_or("Missing command")?;
+    options.argument = positional
+        .next()
+        .ok_or(format!("Missing argument of {} command", options.command))?;
+    if let Some(extra) = positional.next() {
+        return Err(format!("Unexpected argument {extra}"));
+    }
+    Ok(options)
+}
+
+/** Parses into the type of the option, so negative and too big values are rejected instead of wrapped */
+fn parse_number<T: FromStr>(arg: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value of {arg}: {value}"))
+}
+
+fn load_codeowners(options: &Options) -> Owners {
+    let content = git::get_codeowners_content(&options.repo, &options.branch);
+    codeowners::from_reader(content.as_bytes())
+}
+
+fn display_owners(owners: &str) -> &str {
+    if owners.is_empty() {
+        "(unowned)"
+    } else {
+        owners
+    }
+}
+
+fn owners(options: &Options) {
+    let codeowners = load_codeowners(options);
+    let owners = normalize_owners(codeowners.of(&options.argument));
+    if options.json {
+        let result = json!({ "path": options.argument, "owners": owners });
+        println!("{}", serde_json::to_string_pretty(&result).unwrap());
+    } else {
+        println!("{}", display_owners(&join_owners(&owners)));
+    }
+}
+
+fn rule_json(rule: &Rule) -> serde_json::Value {
+    json!({
+        "line": rule.line,
+        "pattern": rule.raw_pattern,
+        "owners": rule.owners,
+    })
+}
+
+fn rule_line(rule: &Rule) -> String {
+    format!(
+        "CODEOWNERS:{} {} {}",
+        rule.line,
+        rule.raw_pattern,
+        join_owners(&rule.owners)
+    )
+}
+
+fn explain(options: &Options) {
+    let codeowners = load_codeowners(options);
+    let rules = codeowners.matching_rules(&options.argument);
+    if options.json {
+        let result = json!({
+            "path": options.argument,
+            "rule": rules.first().map(|rule| rule_json(rule)),
+            "overridden_rules": rules.iter().skip(1).map(|rule| rule_json(rule)).collect::<Vec<_>>(),
+        });
+        println!("{}", serde_json::to_string_pretty(&result).unwrap());
+        return;
+    }
+    match rules.split_first() {
+        None => println!("{}: no rule matches, file is unowned", options.argument),
+        Some((rule, overridden)) => {
+            println!("{}: {}", options.argument, rule_line(rule));
+            if !overridden.is_empty() {
+                println!("Overridden rules:");
+                for rule in overridden {
+                    println!("  {}", rule_line(rule));
+                }
+            }
+        }
+    }
+}
+
+fn status_letter(status: ChangeStatus) -> char {
+    match status {
+        ChangeStatus::Added => 'A',
+        ChangeStatus::Modified => 'M',
+        ChangeStatus::Deleted => 'D',
+        ChangeStatus::Renamed => 'R',
+        ChangeStatus::Copied => 'C',
+    }
+}
+
+fn print_groups(groups: &[FrontendCodeowner]) {
+    for group in groups {
+        println!(
+            "{}: {} files, +{} / -{}",
+            display_owners(&group.label),
+            group.files.len(),
+            group.added_lines,
+            group.deleted_lines
+        );
+        for file in &group.files {
+            let change = &file.change;
+            let path = match &change.old_path {
+                Some(old_path) => format!("{old_path} -> {}", change.path),
+                None => change.path.clone(),
+            };
+            let lines = if change.lines.binary {
+                String::from("binary")
+            } else {
+                format!(
+                    "+{} -{}",
+                    change.lines.added_lines, change.lines.deleted_lines
+                )
+            };
+            println!("  {} {path} {lines}", status_letter(change.status));
+        }
+    }
+}
+
+fn diff(options: &Options) {
+    let result = exit_on_error(branch_diff::get_changed_codeowners(
+        &options.repo,
+        &options.argument,
+        options.codeowners_revision,
+        options.grouping,
+    ));
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&result).unwrap());
+        return;
+    }
+    match (&result.base, &result.head) {
+        (Some(base), Some(head)) => {
+            println!("Base CODEOWNERS:");
+            print_groups(base);
+            println!("\nBranch CODEOWNERS:");
+            print_groups(head);
+        }
+        (Some(groups), None) | (None, Some(groups)) => print_groups(groups),
+        (None, None) => {}
+    }
+    if !result.ownership_changed.is_empty() {
+        println!("\nOwnership changed by this branch:");
+        for change in &result.ownership_changed {
+            println!(
+                "  {}: {} -> {}",
+                change.path,
+                display_owners(&join_owners(&change.base_owners)),
+                display_owners(&join_owners(&change.head_owners))
+            );
+        }
+    }
+}
+
+/** Prints directories whose files have the same owners as one line */
+fn print_tree(directory: &DirectoryOwners, depth: usize) {
+    let indent = "  ".repeat(depth);
+    for sub_directory in &directory.directories {
+        match sub_directory.common_owner() {
+            Some(owner) => println!("{indent}{}/ {}", sub_directory.name, display_owners(owner)),
+            None => {
+                println!("{indent}{}/", sub_directory.name);
+                print_tree(sub_directory, depth + 1);
+            }
+        }
+    }
+    for file in &directory.files {
+        println!("{indent}{} {}", file.name, display_owners(&file.owner));
+    }
+}
+
+fn tree(options: &Options) {
+    let result = all_owners::get_all_codeowners_for_branch_struct(
+        &options.repo,
+        &options.argument,
+        |_, _| {},
+    );
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&result).unwrap());
+    } else {
+        print_tree(&result, 0);
+    }
+}
+
+fn check(options: &Options) {
+    let report = exit_on_error(branch_check::check_branch(
+        &options.repo,
+        &options.argument,
+        options.codeowners_revision,
+        &options.check,
+    ));
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    } else {
+        println!(
+            "Checked {} changed files of {}",
+            report.files_checked, report.branch
+        );
+        for group in &report.violations {
+            let owners = join_owners(&group.owners);
+            let reason = match &group.violation {
+                Violation::Unowned => String::from("no owners"),
+                Violation::NoTeamOwner => format!("no team among owners {owners}"),
+                Violation::TooManyOwners { count, max } => {
+                    format!("{count} owners, at most {max} allowed: {owners}")
+                }
+            };
+            println!("FAIL {reason}");
+            for file in &group.files {
+                println!("  {file}");
+            }
+        }
+        if report.passed() {
+            println!("OK");
+        }
+    }
+    if !report.passed() {
+        process::exit(1);
+    }
+}
+
+fn load_roster(options: &Options) -> Roster {
+    let result = match &options.roster {
+        Some(roster_path) => Roster::load(&options.repo, roster_path),
+        None => Err(String::from("Missing --roster option")),
+    };
+    exit_on_error(result)
+}
+
+/** Roster and git errors exit with code 2, so they are never confused with failed checks */
+fn exit_on_error<T>(result: Result<T, String>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(error) => {
+            eprintln!("{error}");
+            process::exit(2);
+        }
+    }
+}
+
+fn validate(options: &Options) {
+    let roster = load_roster(options);
+    let result = roster::validate_branch_owners(&options.repo, &options.argument, &roster);
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&result).unwrap());
+    } else {
+        for diagnostic in &result.diagnostics {
+            println!("CODEOWNERS {diagnostic}");
+        }
+        for unknown in &result.unknown_owners {
+            let suggestion = match &unknown.suggestion {
+                Some(suggestion) => format!(", did you mean {suggestion}?"),
+                None => String::new(),
+            };
+            println!(
+                "CODEOWNERS line {}: unknown owner {}{suggestion}",
+                unknown.line, unknown.owner
+            );
+        }
+        if result.passed() {
+            println!("OK");
+        }
+    }
+    if !result.passed() {
+        process::exit(1);
+    }
+}
+
+fn review(options: &Options) {
+    let roster = load_roster(options);
+    let me = match &options.me {
+        Some(me) => me,
+        None => {
+            eprintln!("Missing --me option\n\n{USAGE}");
+            process::exit(2);
+        }
+    };
+    let result = exit_on_error(personal::get_review_files(
+        &options.repo,
+        &options.argument,
+        options.codeowners_revision,
+        &roster,
+        me,
+    ));
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&result).unwrap());
+        return;
+    }
+    println!(
+        "{} changed files need review of {}",
+        result.files.len(),
+        result.person
+    );
+    for review in &result.files {
+        let change = &review.file.change;
+        println!(
+            "  {} {} via {}",
+            status_letter(change.status),
+            change.path,
+            join_owners(&review.via)
+        );
+    }
+}
+
+fn owned(options: &Options) {
+    let roster = load_roster(options);
+    let result =
+        personal::get_owned_files(&options.repo, &options.branch, &roster, &options.argument);
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&result).unwrap());
+        return;
+    }
+    println!(
+        "{} owns {} of {} files",
+        result.person,
+        result.files.len(),
+        result.files_total
+    );
+    for file in &result.files {
+        println!("  {} via {}", file.path, join_owners(&file.via));
+    }
+}
+
+fn minimal_reviewers(options: &Options) {
+    let result = exit_on_error(reviewers::get_minimal_reviewers(
+        &options.repo,
+        &options.argument,
+        options.codeowners_revision,
+    ));
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&result).unwrap());
+        return;
+    }
+    for reviewer in &result.reviewers {
+        println!("{}: {} files", reviewer.reviewer, reviewer.files.len());
+        for file in &reviewer.files {
+            println!("  {file}");
+        }
+    }
+    if !result.unowned_files.is_empty() {
+        println!("Nobody owns {} files:", result.unowned_files.len());
+        for file in &result.unowned_files {
+            println!("  {file}");
+        }
+    }
+    if !result.exact {
+        println!("Too many owners for exact search, the set may be not the smallest");
+    }
+}
+
+fn simulate_approvals(options: &Options) {
+    // roster is optional here, without it only direct owners can approve
+    let roster = exit_on_error(match &options.roster {
+        Some(roster_path) => Roster::load(&options.repo, roster_path),
+        None => Ok(Roster::default()),
+    });
+    let report = exit_on_error(approvals::simulate_branch_approvals(
+        &options.repo,
+        &options.argument,
+        options.codeowners_revision,
+        &roster,
+        options.approvers.clone(),
+        options.gitlab_sections,
+    ));
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    } else {
+        println!("Approved by: {}", report.approvers.join(", "));
+        for section in report.sections.iter().flatten() {
+            let name = section.section.as_deref().unwrap_or("(no section)");
+            let state = if section.optional {
+                String::from("optional")
+            } else if section.missing > 0 {
+                format!("needs {} more of {}", section.missing, section.required)
+            } else {
+                String::from("approved")
+            };
+            println!("[{name}] {state}, {} files", section.files.len());
+        }
+        for file in &report.blocked_files {
+            match &file.section {
+                Some(section) => println!(
+                    "BLOCKED {} [{section}] {}",
+                    file.path,
+                    join_owners(&file.owners)
+                ),
+                None => println!("BLOCKED {} {}", file.path, join_owners(&file.owners)),
+            }
+        }
+        if report.approved() {
+            println!("OK");
+        }
+    }
+    if !report.approved() {
+        process::exit(1);
+    }
+}
+
+fn summary(options: &Options) {
+    let result = pr_summary::get_pull_request_summary(
+        &options.repo,
+        &options.argument,
+        options.codeowners_revision,
+        options.template.as_deref(),
+    );
+    match result {
+        Ok(markdown) => print!("{markdown}"),
+        Err(error) => {
+            eprintln!("{error}");
+            process::exit(2);
+        }
+    }
+}
+
+fn export(options: &Options) {
+    let write = |writer: &mut dyn Write| {
+        if options.command == "export" {
+            export::write_all_owners(
+                writer,
+                &options.repo,
+                &options.argument,
+                options.format,
+                &ExportFilter::default(),
+            )
+        } else {
+            export::write_branch_changes(
+                writer,
+                &options.repo,
+                &options.argument,
+                options.codeowners_revision,
+                options.format,
+            )
+        }
+    };
+    let result = match &options.output {
+        Some(path) => export::export_to_file(path, |writer| write(writer)).map(|_| ()),
+        None => {
+            let mut writer = BufWriter::new(io::stdout().lock());
+            write(&mut writer)
+                .and_then(|_| writer.flush())
+                .map_err(|error| error.to_string())
+        }
+    };
+    if let Err(error) = result {
+        eprintln!("{error}");
+        process::exit(2);
+    }
+}
+
+fn print_totals(name: &str, totals: &Totals) {
+    println!(
+        "  {name}: {} files, {} bytes, {} lines{}",
+        totals.files,
+        totals.bytes,
+        totals.lines,
+        if totals.binary_files > 0 {
+            format!(", {} binary files", totals.binary_files)
+        } else {
+            String::new()
+        }
+    );
+}
+
+fn stats(options: &Options) {
+    let result = statistics::get_repo_statistics(&options.repo, &options.argument);
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&result).unwrap());
+        return;
+    }
+    println!("Tree {}", result.tree);
+    print_totals("Total", &result.total);
+    println!("Owners:");
+    for stats in &result.owners {
+        let owner = stats.owner.as_ref().map(|owner| owner.to_string());
+        print_totals(
+            display_owners(owner.as_deref().unwrap_or_default()),
+            &stats.totals,
+        );
+    }
+    println!("Extensions:");
+    for stats in &result.extensions {
+        let extension = if stats.extension.is_empty() {
+            "(no extension)"
+        } else {
+            &stats.extension
+        };
+        print_totals(extension, &stats.totals);
+    }
+}
+
+fn print_coverage(options: &Options) {
+    let report =
+        coverage::get_coverage_report(&options.repo, &options.argument, options.depth, options.top);
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    } else {
+        print!("{}", coverage::coverage_markdown(&report));
+    }
+}
+
+fn trend(options: &Options) {
+    let trend = exit_on_error(coverage_trend::get_coverage_trend(
+        &options.repo,
+        &options.argument,
+        options.interval_days,
+    ));
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&trend).unwrap());
+        return;
+    }
+    println!("Date        Commit    Files   Bytes   Lines   Owners");
+    for point in &trend.points {
+        println!(
+            "{}  {:.8}  {:>5.1}%  {:>5.1}%  {:>5.1}%  {}",
+            point.date,
+            point.commit,
+            point.coverage.files.percent,
+            point.coverage.bytes.percent,
+            point.coverage.lines.percent,
+            point.coverage.owners
+        );
+    }
+}
+
+/** Roster is optional for history commands, without it authors are not matched with teams */
+fn load_optional_roster(options: &Options) -> Roster {
+    exit_on_error(match &options.roster {
+        Some(roster_path) => Roster::load(&options.repo, roster_path),
+        None => Ok(Roster::default()),
+    })
+}
+
+fn suggest(options: &Options) {
+    let roster = load_optional_roster(options);
+    let result = owner_suggestions::get_owner_suggestions(
+        &options.repo,
+        &options.argument,
+        options.months.unwrap_or(owner_suggestions::DEFAULT_MONTHS),
+        options.max_authors,
+        &roster,
+    );
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&result).unwrap());
+        return;
+    }
+    // the output can be appended to CODEOWNERS as it is
+    for suggestion in &result.suggestions {
+        match (&suggestion.line, &suggestion.warning) {
+            (Some(line), _) => println!("{line}"),
+            (None, Some(warning)) => {
+                eprintln!("{}: {warning}", suggestion.path);
+                println!(
+                    "# {}: {}",
+                    suggestion.path,
+                    suggestion.suggested_owners.join(" ")
+                );
+            }
+            (None, None) => println!("# {}: nobody to suggest", suggestion.path),
+        }
+    }
+}
+
+fn print_drift(options: &Options) {
+    let roster = load_optional_roster(options);
+    let report = drift::get_ownership_drift(
+        &options.repo,
+        &options.argument,
+        options.months.unwrap_or(drift::DEFAULT_MONTHS),
+        &roster,
+    );
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        return;
+    }
+    let drifted: Vec<&drift::RuleDrift> =
+        report.rules.iter().filter(|rule| rule.drifted()).collect();
+    let unknown = report.rules.iter().filter(|rule| rule.unknown()).count();
+    if unknown > 0 {
+        eprintln!(
+            "{unknown} changed rules are owned by teams whose members the roster doesn't list, they are not checked"
+        );
+    }
+    if drifted.is_empty() {
+        println!("No drift in the last {} months", report.months);
+        return;
+    }
+    for rule in drifted {
+        println!(
+            "CODEOWNERS line {}: {} {}",
+            rule.line,
+            rule.pattern,
+            join_owners(&rule.owners)
+        );
+        if rule.owners_never_commit {
+            println!("  owners made none of {} commits", rule.commits);
+        }
+        if let Some(author) = &rule.dominant_non_owner {
+            println!(
+                "  {} made {} of {} commits without being an owner",
+                author.author, author.commits, rule.commits
+            );
+        }
+    }
+}
+
+fn print_knowledge(options: &Options) {
+    let roster = load_optional_roster(options);
+    let report = knowledge::get_knowledge_report(
+        &options.repo,
+        &options.argument,
+        options.months.unwrap_or(knowledge::DEFAULT_MONTHS),
+        options.depth,
+        &roster,
+    );
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        return;
+    }
+    print_concentration("Total", &report.total);
+    println!("Owners:");
+    for knowledge in &report.owners {
+        let owner = knowledge.owner.as_ref().map(|owner| owner.to_string());
+        print_concentration(
+            display_owners(owner.as_deref().unwrap_or_default()),
+            &knowledge.concentration,
+        );
+    }
+    println!("Directories:");
+    for knowledge in &report.directories {
+        print_concentration(&knowledge.path, &knowledge.concentration);
+    }
+}
+
+fn print_concentration(name: &str, concentration: &knowledge::Concentration) {
+    match &concentration.top_contributor {
+        Some(top_contributor) => println!(
+            "  {name}: bus factor {}, {} contributors, {} commits, {top_contributor} changed {}% of {} lines",
+            concentration.bus_factor,
+            concentration.contributors,
+            concentration.commits,
+            concentration.top_share,
+            concentration.lines
+        ),
+        None => println!("  {name}: no changes"),
+    }
+}
+
+fn print_churn(options: &Options) {
+    let report = churn::get_churn_report(
+        &options.repo,
+        &options.argument,
+        options.months.unwrap_or(churn::DEFAULT_MONTHS),
+        options.depth,
+    );
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        return;
+    }
+    println!(
+        "Total: {} commits, {} lines in the last {} months",
+        report.total.commits, report.total.lines, report.months
+    );
+    println!("Owners:");
+    for group in &report.owners {
+        println!(
+            "  {}: {} commits, {} lines",
+            display_owners(&group.owner),
+            group.churn.commits,
+            group.churn.lines
+        );
+    }
+    println!("Directories:");
+    for directory in report
+        .directories
+        .iter()
+        .filter(|dir| dir.churn.commits > 0)
+    {
+        let cells: Vec<String> = report
+            .owners
+            .iter()
+            .zip(&directory.owners)
+            .filter(|(_, cell)| cell.commits > 0)
+            .map(|(group, cell)| format!("{} {}", display_owners(&group.owner), cell.lines))
+            .collect();
+        println!(
+            "  {}: {} commits, {} lines ({})",
+            directory.path,
+            directory.churn.commits,
+            directory.churn.lines,
+            cells.join(", ")
+        );
+    }
+}
+
+fn history(options: &Options) {
+    let history = exit_on_error(ownership_history::get_ownership_history(
+        &options.repo,
+        &options.branch,
+        &options.argument,
+    ));
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&history).unwrap());
+        return;
+    }
+    if history.intervals.is_empty() {
+        println!("CODEOWNERS never existed in {}", history.branch);
+        return;
+    }
+    println!("Since       Until       Commit    Owners");
+    for interval in &history.intervals {
+        let rule = match (&interval.codeowners_path, &interval.rule) {
+            (Some(location), Some(rule)) => format!(" ({location}:{} {})", rule.line, rule.pattern),
+            _ => String::new(),
+        };
+        println!(
+            "{}  {:<10}  {:.8}  {}{rule}",
+            interval.date,
+            interval.until_date.as_deref().unwrap_or("now"),
+            interval.commit,
+            display_owners(&join_owners(&interval.owners))
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()).collect())
+    }
+
+    #[test]
+    fn parses_options_and_positional_arguments() {
+        let options = parse(&[
+            "trend",
+            "main",
+            "--interval",
+            "14",
+            "--months",
+            "6",
+            "--max-owners",
+            "3",
+            "--codeowners",
+            "both",
+            "--json",
+        ])
+        .ok()
+        .unwrap();
+        assert_eq!(options.command, "trend");
+        assert_eq!(options.argument, "main");
+        assert_eq!(options.interval_days, 14);
+        assert_eq!(options.months, Some(6));
+        assert_eq!(options.check.max_owners, Some(3));
+        assert_eq!(options.codeowners_revision, CodeownersRevision::Both);
+        assert!(options.json);
+        assert_eq!(options.repo, ".");
+        assert_eq!(options.depth, coverage::DEFAULT_DEPTH);
+    }
+
+    #[test]
+    fn rejects_numbers_out_of_range() {
+        assert_eq!(
+            parse(&["trend", "main", "--interval", "-1"]).err(),
+            Some(String::from("Invalid value of --interval: -1"))
+        );
+        assert_eq!(
+            parse(&["drift", "main", "--months", "4294967296"]).err(),
+            Some(String::from("Invalid value of --months: 4294967296"))
+        );
+        assert_eq!(
+            parse(&["coverage", "main", "--depth", "two"]).err(),
+            Some(String::from("Invalid value of --depth: two"))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert_eq!(
+            parse(&["check", "main", "--max-owners"]).err(),
+            Some(String::from("Missing value of --max-owners"))
+        );
+        assert_eq!(
+            parse(&["check", "main", "--verbose"]).err(),
+            Some(String::from("Unknown option --verbose"))
+        );
+        assert_eq!(
+            parse(&["check", "main", "other"]).err(),
+            Some(String::from("Unexpected argument other"))
+        );
+        assert_eq!(
+            parse(&["check"]).err(),
+            Some(String::from("Missing argument of check command"))
+        );
+        assert!(parse(&["diff", "main", "--codeowners", "tip"]).is_err());
+    }
+}
@@ -0,0 +1,219 @@
+//! Checks of branch diff ownership which can fail pre-push scripts and CI jobs
+use serde::Serialize;
+use std::collections::HashSet;
+
+use crate::branch_diff::{self, CodeownersRevision, FrontendCodeowner, OwnersGrouping};
+use codeowners::Owner;
+
+/** Conditions which make the check fail */
+#[derive(Clone, Debug, PartialEq)]
+pub struct CheckOptions {
+    /** Fail if some changed file has no owners */
+    pub fail_on_unowned: bool,
+    /** Fail if some changed file is owned only by users or emails without any team */
+    pub require_team: bool,
+    /** Fail if some changed file has more owners than this */
+    pub max_owners: Option<usize>,
+}
+
+impl Default for CheckOptions {
+    fn default() -> Self {
+        CheckOptions {
+            fail_on_unowned: true,
+            require_team: false,
+            max_owners: None,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Violation {
+    Unowned,
+    NoTeamOwner,
+    TooManyOwners { count: usize, max: usize },
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct GroupViolation {
+    #[serde(flatten)]
+    pub violation: Violation,
+    pub owners: Vec<Owner>,
+    pub files: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct CheckReport {
+    pub branch: String,
+    pub files_checked: usize,
+    pub violations: Vec<GroupViolation>,
+}
+
+impl CheckReport {
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/**
+ * Checks changed files of the branch grouped the same way as `get_changed_codeowners` does.
+ * Fails if git fails, e.g. main is missing, so a broken setup never passes the check
+ */
+pub fn check_branch(
+    abs_repo_path: &str,
+    branch: &str,
+    revision: CodeownersRevision,
+    options: &CheckOptions,
+) -> Result<CheckReport, String> {
+    let result =
+        branch_diff::get_changed_codeowners(abs_repo_path, branch, revision, OwnersGrouping::Set)?;
+    let groups: Vec<&FrontendCodeowner> = result
+        .base
+        .iter()
+        .chain(result.head.iter())
+        .flatten()
+        .collect();
+    Ok(check_groups(branch, &groups, options))
+}
+
+/** Groups of base and head can list the same file, it is reported once per violation */
+pub fn check_groups(
+    branch: &str,
+    groups: &[&FrontendCodeowner],
+    options: &CheckOptions,
+) -> CheckReport {
+    let mut files_checked: Vec<&str> = Vec::new();
+    let mut violations: Vec<GroupViolation> = Vec::new();
+    let mut reported: HashSet<(Violation, &str)> = HashSet::new();
+    for group in groups {
+        files_checked.extend(group.files.iter().map(|file| file.change.path.as_str()));
+        let owners = &group.owners;
+        let mut group_violations: Vec<Violation> = Vec::new();
+        if owners.is_empty() {
+            if options.fail_on_unowned {
+                group_violations.push(Violation::Unowned);
+            }
+        } else if options.require_team
+            && !owners.iter().any(|owner| matches!(owner, Owner::Team(_)))
+        {
+            group_violations.push(Violation::NoTeamOwner);
+        }
+        if let Some(max) = options.max_owners.filter(|max| owners.len() > *max) {
+            group_violations.push(Violation::TooManyOwners {
+                count: owners.len(),
+                max,
+            });
+        }
+        for violation in group_violations {
+            let files: Vec<String> = group
+                .files
+                .iter()
+                .map(|file| file.change.path.as_str())
+                .filter(|path| reported.insert((violation.clone(), path)))
+                .map(String::from)
+                .collect();
+            if !files.is_empty() {
+                violations.push(GroupViolation {
+                    violation,
+                    owners: group.owners.clone(),
+                    files,
+                });
+            }
+        }
+    }
+    files_checked.sort_unstable();
+    files_checked.dedup();
+    CheckReport {
+        branch: branch.to_string(),
+        files_checked: files_checked.len(),
+        violations,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::branch_diff::{group_changes_by_owners, parse_name_status, OwnersGrouping};
+
+    const CODEOWNERS: &str = r"/payments/ @org/payments
+/scripts/ @alice @bob
+/shared/ @org/a @org/b @org/c
+";
+
+    fn check(options: &CheckOptions) -> CheckReport {
+        let codeowners = codeowners::from_reader(CODEOWNERS.as_bytes());
+        let changes = parse_name_status(
+            "M\0payments/api.rs\0A\0README.md\0M\0scripts/run.sh\0M\0shared/lib.rs\0",
+        );
+        let groups =
+            group_changes_by_owners(&changes, &codeowners, &codeowners, OwnersGrouping::Set);
+        check_groups("feature", &groups.iter().collect::<Vec<_>>(), options)
+    }
+
+    #[test]
+    fn fails_on_unowned_by_default() {
+        let report = check(&CheckOptions::default());
+        assert_eq!(report.files_checked, 4);
+        assert_eq!(
+            report.violations,
+            vec![GroupViolation {
+                violation: Violation::Unowned,
+                owners: vec![],
+                files: vec!["README.md".into()],
+            }]
+        );
+    }
+
+    #[test]
+    fn reports_file_once_for_base_and_head() {
+        let base = codeowners::from_reader("/payments/ @org/payments\n".as_bytes());
+        let head = codeowners::from_reader("/payments/api.rs @alice\n".as_bytes());
+        let changes = parse_name_status("M\0README.md\0M\0payments/api.rs\0");
+        let base_groups = group_changes_by_owners(&changes, &base, &base, OwnersGrouping::Set);
+        let head_groups = group_changes_by_owners(&changes, &base, &head, OwnersGrouping::Set);
+        let groups: Vec<&FrontendCodeowner> = base_groups.iter().chain(&head_groups).collect();
+        let report = check_groups(
+            "feature",
+            &groups,
+            &CheckOptions {
+                require_team: true,
+                ..CheckOptions::default()
+            },
+        );
+        assert_eq!(report.files_checked, 2);
+        assert_eq!(
+            report
+                .violations
+                .iter()
+                .map(|group| (group.violation.clone(), group.files.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (Violation::Unowned, vec![String::from("README.md")]),
+                (
+                    Violation::NoTeamOwner,
+                    vec![String::from("payments/api.rs")]
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_team_owners_and_owners_count() {
+        let report = check(&CheckOptions {
+            fail_on_unowned: false,
+            require_team: true,
+            max_owners: Some(2),
+        });
+        assert_eq!(
+            report
+                .violations
+                .iter()
+                .map(|group| group.violation.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                Violation::NoTeamOwner,
+                Violation::TooManyOwners { count: 3, max: 2 }
+            ]
+        );
+    }
+}
@@ -0,0 +1,119 @@
+//! Codeowners touched by every commit of a branch
+use serde::Serialize;
+
+use crate::branch_diff::{
+    self, BranchCodeownersFiles, ChangedFile, CodeownersRevision, FrontendCodeowner, OwnersGrouping,
+};
+use crate::git::{self, Commit};
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct CommitCodeowners {
+    #[serde(flatten)]
+    pub commit: Commit,
+    /** Files of the commit grouped with base CODEOWNERS. Null unless base or both revisions requested */
+    pub base: Option<Vec<FrontendCodeowner>>,
+    /** Files of the commit grouped with branch CODEOWNERS. Null unless head or both revisions requested */
+    pub head: Option<Vec<FrontendCodeowner>>,
+}
+
+/**
+ * Returns commits of the branch which are not in main, oldest first.
+ * Owners are resolved the same way as for the whole branch diff,
+ * so every commit shows part of `get_changed_codeowners` result.
+ */
+pub fn get_commits_codeowners(
+    abs_repo_path: &str,
+    branch: &str,
+    revision: CodeownersRevision,
+    grouping: OwnersGrouping,
+) -> Result<Vec<CommitCodeowners>, String> {
+    let codeowners = BranchCodeownersFiles::load(abs_repo_path, branch)?;
+    let commits = git::get_commit_diffs(abs_repo_path, &format!("{}..{branch}", git::BASE_BRANCH))?;
+    Ok(commits
+        .into_iter()
+        .map(|(commit, diff)| {
+            let changes = branch_diff::parse_raw_numstat(&diff);
+            commit_codeowners(&codeowners, commit, &changes, revision, grouping)
+        })
+        .collect())
+}
+
+/** Groups files changed by the commit with CODEOWNERS of the whole branch */
+pub fn commit_codeowners(
+    codeowners: &BranchCodeownersFiles,
+    commit: Commit,
+    changes: &[ChangedFile],
+    revision: CodeownersRevision,
+    grouping: OwnersGrouping,
+) -> CommitCodeowners {
+    CommitCodeowners {
+        base: codeowners.base_groups(changes, revision, grouping),
+        head: codeowners.head_groups(changes, revision, grouping),
+        commit,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::branch_diff::ChangeStatus;
+
+    fn labels(groups: &Option<Vec<FrontendCodeowner>>) -> Option<Vec<(&str, usize)>> {
+        groups.as_ref().map(|groups| {
+            groups
+                .iter()
+                .map(|group| (group.label.as_str(), group.files.len()))
+                .collect()
+        })
+    }
+
+    #[test]
+    fn groups_files_of_every_commit() {
+        let codeowners = BranchCodeownersFiles::parse(
+            String::from("* @org/core\n"),
+            String::from("* @org/core\n/payments/ @org/payments\n"),
+        );
+        let output = "\x1ea1\x1fJane\x1fjane@example.com\x1f1700000000\x1fAdd payments\0\n\
+             :000000 100644 0000000 1111111 A\0payments/api.rs\0\
+             :100644 100644 2222222 3333333 M\0src/main.rs\0\
+             :000000 100644 0000000 4444444 A\0payments/db.rs\0\
+             10\t0\tpayments/api.rs\x002\t1\tsrc/main.rs\0-\t-\tpayments/db.rs\0\
+             \x1eb2\x1fJohn\x1fjohn@example.com\x1f1700000100\x1fMove docs\0\n\
+             :100644 100644 5555555 5555555 R100\0docs/a.md\0payments/a.md\0\
+             0\t0\t\0docs/a.md\0payments/a.md\0";
+        let result: Vec<CommitCodeowners> = git::parse_log_diffs(output)
+            .into_iter()
+            .map(|(commit, diff)| {
+                commit_codeowners(
+                    &codeowners,
+                    commit,
+                    &branch_diff::parse_raw_numstat(&diff),
+                    CodeownersRevision::Both,
+                    OwnersGrouping::Set,
+                )
+            })
+            .collect();
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].commit.subject, "Add payments");
+        assert_eq!(labels(&result[0].base), Some(vec![("@org/core", 3)]));
+        assert_eq!(
+            labels(&result[0].head),
+            Some(vec![("@org/core", 1), ("@org/payments", 2)])
+        );
+        assert_eq!(result[0].head.as_ref().unwrap()[1].added_lines, 10);
+        let renamed = &result[1].head.as_ref().unwrap()[0].files[0];
+        assert_eq!(renamed.change.status, ChangeStatus::Renamed);
+        assert_eq!(renamed.change.old_path.as_deref(), Some("docs/a.md"));
+
+        let head_only = commit_codeowners(
+            &codeowners,
+            result[1].commit.clone(),
+            &[],
+            CodeownersRevision::Head,
+            OwnersGrouping::Set,
+        );
+        assert_eq!(head_only.base, None);
+        assert_eq!(labels(&head_only.head), Some(vec![]));
+    }
+}
@@ -0,0 +1,621 @@
+//! Codeowners of files changed in a branch comparing with main
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::git;
+use crate::{join_owners, normalize_owners};
+use codeowners::{Owner, Owners};
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeStatus {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+    Copied,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ChangedFile {
+    pub status: ChangeStatus,
+    /** Path on the branch. For deleted files it is path on the base */
+    pub path: String,
+    /** Path before rename or copy */
+    pub old_path: Option<String>,
+    #[serde(flatten)]
+    pub lines: LineStats,
+}
+
+/** Count of changed lines like `git diff --numstat` reports */
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Default)]
+pub struct LineStats {
+    pub added_lines: u32,
+    pub deleted_lines: u32,
+    /** Lines are not counted for binary files */
+    pub binary: bool,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct FrontendChangedFile {
+    #[serde(flatten)]
+    pub change: ChangedFile,
+    /** Owners of the old path on the base. Set only for renamed files */
+    pub old_owners: Option<Vec<Owner>>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct FrontendCodeowner {
+    /// Codeowners sorted by kind and name. Empty for unowned files
+    pub owners: Vec<Owner>,
+    /** Owners joined with ", ", stable key of the group */
+    pub label: String,
+    pub files: Vec<FrontendChangedFile>,
+    /** Sum of added lines of all files in the group */
+    pub added_lines: u64,
+    /** Sum of deleted lines of all files in the group */
+    pub deleted_lines: u64,
+}
+
+/** How changed files are split into groups */
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OwnersGrouping {
+    /** File is listed once under its exact set of owners */
+    #[default]
+    Set,
+    /** File is listed under every one of its owners, unowned files are listed under empty owners */
+    Individual,
+}
+
+impl FromStr for OwnersGrouping {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "set" => Ok(OwnersGrouping::Set),
+            "individual" => Ok(OwnersGrouping::Individual),
+            _ => Err(format!(
+                "unknown owners grouping {s}, expected set or individual"
+            )),
+        }
+    }
+}
+
+/** Which CODEOWNERS revision is used to resolve owners of changed files */
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CodeownersRevision {
+    /**
+     * CODEOWNERS of the merge-base, the commit branch was forked from. GitHub resolves
+     * required reviewers with the tip of the base branch, which differs once main moves on
+     */
+    Base,
+    /** CODEOWNERS of the branch itself */
+    #[default]
+    Head,
+    /** Both of them side by side */
+    Both,
+}
+
+impl FromStr for CodeownersRevision {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "base" => Ok(CodeownersRevision::Base),
+            "head" => Ok(CodeownersRevision::Head),
+            "both" => Ok(CodeownersRevision::Both),
+            _ => Err(format!(
+                "unknown codeowners revision {s}, expected base, head or both"
+            )),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct OwnershipChange {
+    pub path: String,
+    pub base_owners: Vec<Owner>,
+    pub head_owners: Vec<Owner>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct BranchCodeowners {
+    /** Changed files grouped with base CODEOWNERS. Null unless base or both revisions requested */
+    pub base: Option<Vec<FrontendCodeowner>>,
+    /** Changed files grouped with branch CODEOWNERS. Null unless head or both revisions requested */
+    pub head: Option<Vec<FrontendCodeowner>>,
+    /** Files of the branch whose owners are changed by this branch. Empty if branch keeps CODEOWNERS as is */
+    pub ownership_changed: Vec<OwnershipChange>,
+}
+
+/** Parsed CODEOWNERS of the branch and of the commit it was forked from */
+pub struct BranchCodeownersFiles {
+    pub base_content: String,
+    pub base: Owners,
+    pub head_content: String,
+    pub head: Owners,
+}
+
+impl BranchCodeownersFiles {
+    /** Fails if the branch has no merge base with main, e.g. in shallow clones without main */
+    pub fn load(abs_repo_path: &str, branch: &str) -> Result<BranchCodeownersFiles, String> {
+        let merge_base = git::get_merge_base(abs_repo_path, git::BASE_BRANCH, branch)?;
+        // missing CODEOWNERS is not an error, all files are unowned then
+        let head_content = git::get_codeowners_content(abs_repo_path, branch);
+        let base_content = git::get_codeowners_content(abs_repo_path, &merge_base);
+        Ok(BranchCodeownersFiles::parse(base_content, head_content))
+    }
+
+    pub fn parse(base_content: String, head_content: String) -> BranchCodeownersFiles {
+        BranchCodeownersFiles {
+            base: codeowners::from_reader(base_content.as_bytes()),
+            head: codeowners::from_reader(head_content.as_bytes()),
+            base_content,
+            head_content,
+        }
+    }
+
+    /** Changes grouped with base CODEOWNERS. None if requested revision doesn't include base */
+    pub fn base_groups(
+        &self,
+        changes: &[ChangedFile],
+        revision: CodeownersRevision,
+        grouping: OwnersGrouping,
+    ) -> Option<Vec<FrontendCodeowner>> {
+        match revision {
+            CodeownersRevision::Head => None,
+            _ => Some(group_changes_by_owners(
+                changes, &self.base, &self.base, grouping,
+            )),
+        }
+    }
+
+    /** Changes grouped with branch CODEOWNERS. None if requested revision doesn't include head */
+    pub fn head_groups(
+        &self,
+        changes: &[ChangedFile],
+        revision: CodeownersRevision,
+        grouping: OwnersGrouping,
+    ) -> Option<Vec<FrontendCodeowner>> {
+        match revision {
+            CodeownersRevision::Base => None,
+            _ => Some(group_changes_by_owners(
+                changes, &self.base, &self.head, grouping,
+            )),
+        }
+    }
+}
+
+/** Changed files for branch grouped by owners from requested CODEOWNERS revision */
+pub fn get_changed_codeowners(
+    abs_repo_path: &str,
+    branch: &str,
+    revision: CodeownersRevision,
+    grouping: OwnersGrouping,
+) -> Result<BranchCodeowners, String> {
+    let codeowners = BranchCodeownersFiles::load(abs_repo_path, branch)?;
+    let changes = get_branch_changes(abs_repo_path, branch)?;
+
+    let ownership_changed = if codeowners.base_content == codeowners.head_content {
+        vec![]
+    } else {
+        let files = git::get_branch_files_vector(abs_repo_path, branch);
+        get_ownership_changes(&files, &codeowners.base, &codeowners.head)
+    };
+    Ok(BranchCodeowners {
+        base: codeowners.base_groups(&changes, revision, grouping),
+        head: codeowners.head_groups(&changes, revision, grouping),
+        ownership_changed,
+    })
+}
+
+/** Returns files whose owners differ between base and head CODEOWNERS */
+pub fn get_ownership_changes(
+    files: &[String],
+    base_codeowners: &Owners,
+    head_codeowners: &Owners,
+) -> Vec<OwnershipChange> {
+    files
+        .iter()
+        .filter_map(|file_path| {
+            let base_owners = normalize_owners(base_codeowners.of(file_path));
+            let head_owners = normalize_owners(head_codeowners.of(file_path));
+            if base_owners == head_owners {
+                None
+            } else {
+                Some(OwnershipChange {
+                    path: file_path.to_string(),
+                    base_owners,
+                    head_owners,
+                })
+            }
+        })
+        .collect()
+}
+
+/**
+ * Deleted files are resolved with codeowners of the base, other files with `head_codeowners`.
+ * Pass base codeowners as `head_codeowners` to resolve everything with the base.
+ * Renamed file is listed under owners of its new path and under owners of its old path on the base,
+ * because moving file out of team directory still requires that team review.
+ * With individual grouping file is listed under every owner of both old and new path only once.
+ */
+pub fn group_changes_by_owners(
+    changes: &[ChangedFile],
+    base_codeowners: &Owners,
+    head_codeowners: &Owners,
+    grouping: OwnersGrouping,
+) -> Vec<FrontendCodeowner> {
+    let mut owners_dictionary: HashMap<Vec<Owner>, Vec<FrontendChangedFile>> = HashMap::new();
+    for change in changes {
+        let owners = match change.status {
+            ChangeStatus::Deleted => base_codeowners.of(&change.path),
+            _ => head_codeowners.of(&change.path),
+        };
+        let owners = normalize_owners(owners);
+        let old_owners = match (change.status, &change.old_path) {
+            (ChangeStatus::Renamed, Some(old_path)) => {
+                Some(normalize_owners(base_codeowners.of(old_path)))
+            }
+            _ => None,
+        };
+        let file = FrontendChangedFile {
+            change: change.clone(),
+            old_owners: old_owners.clone(),
+        };
+        let mut keys: Vec<Vec<Owner>> = vec![owners];
+        // a file renamed from an unowned path is not unowned anymore
+        keys.extend(old_owners.filter(|old_owners| !old_owners.is_empty()));
+        if grouping == OwnersGrouping::Individual {
+            keys = keys
+                .into_iter()
+                .flat_map(|owners| {
+                    if owners.is_empty() {
+                        vec![owners]
+                    } else {
+                        owners.into_iter().map(|owner| vec![owner]).collect()
+                    }
+                })
+                .collect();
+        }
+        keys.sort();
+        keys.dedup();
+        for key in keys {
+            owners_dictionary.entry(key).or_default().push(file.clone());
+        }
+    }
+    let mut result: Vec<FrontendCodeowner> = owners_dictionary
+        .into_iter()
+        .map(|(owners, files)| FrontendCodeowner {
+            added_lines: files
+                .iter()
+                .map(|f| f.change.lines.added_lines as u64)
+                .sum(),
+            deleted_lines: files
+                .iter()
+                .map(|f| f.change.lines.deleted_lines as u64)
+                .sum(),
+            label: join_owners(&owners),
+            owners,
+            files,
+        })
+        .collect::<Vec<FrontendCodeowner>>();
+    // We have to send stable data
+    result.sort_by(|a, b| a.label.cmp(&b.label));
+    result
+}
+
+/** Returns changed files between passed branch and main with detected renames and line counts */
+pub fn get_branch_changes(abs_repo_path: &str, branch: &str) -> Result<Vec<ChangedFile>, String> {
+    get_changes(abs_repo_path, &format!("{}...{branch}", git::BASE_BRANCH))
+}
+
+/** Returns changed files of passed `git diff` revision range with detected renames and line counts */
+pub fn get_changes(abs_repo_path: &str, range: &str) -> Result<Vec<ChangedFile>, String> {
+    let output = git::try_run_git(
+        abs_repo_path,
+        &["diff", "--raw", "--numstat", "-z", "-M", "-C", range],
+    )?;
+    Ok(parse_raw_numstat(&output))
+}
+
+/**
+ * Parses output of `git diff --raw --numstat -z`, so statuses and line counts come from one
+ * git call. Raw entries go first, each is `:<modes> <blobs> <status>` followed by its paths
+ */
+pub fn parse_raw_numstat(output: &str) -> Vec<ChangedFile> {
+    let mut name_status: Vec<&str> = Vec::new();
+    let mut numstat: Vec<&str> = Vec::new();
+    let mut parts = output.split('\0');
+    while let Some(part) = parts.next() {
+        let status = match part.strip_prefix(':') {
+            Some(raw) => raw.rsplit(' ').next().unwrap_or_default(),
+            None => {
+                numstat.push(part);
+                numstat.extend(parts.by_ref());
+                break;
+            }
+        };
+        let paths = if status.starts_with(['R', 'C']) { 2 } else { 1 };
+        name_status.push(status);
+        name_status.extend(parts.by_ref().take(paths));
+    }
+    let lines = parse_numstat(&numstat.join("\0"));
+    let mut changes = parse_name_status(&name_status.join("\0"));
+    for change in changes.iter_mut() {
+        if let Some(stats) = lines.get(&change.path) {
+            change.lines = *stats;
+        }
+    }
+    changes
+}
+
+/**
+ * Parses output of `git diff --name-status -z`.
+ * Every entry is a status followed by path, renamed and copied entries have old and new paths.
+ */
+pub fn parse_name_status(output: &str) -> Vec<ChangedFile> {
+    let mut changes: Vec<ChangedFile> = Vec::new();
+    let mut parts = output.split('\0').filter(|part| !part.is_empty());
+    while let Some(status) = parts.next() {
+        let status = match status.chars().next() {
+            Some('A') => ChangeStatus::Added,
+            Some('D') => ChangeStatus::Deleted,
+            Some('R') => ChangeStatus::Renamed,
+            Some('C') => ChangeStatus::Copied,
+            // type changes and unmerged files are modifications of the same path
+            _ => ChangeStatus::Modified,
+        };
+        let old_path = match status {
+            ChangeStatus::Renamed | ChangeStatus::Copied => parts.next().map(String::from),
+            _ => None,
+        };
+        if let Some(path) = parts.next() {
+            changes.push(ChangedFile {
+                status,
+                path: path.to_string(),
+                old_path,
+                lines: LineStats::default(),
+            });
+        }
+    }
+    changes
+}
+
+/**
+ * Parses output of `git diff --numstat -z`. Key is path on the branch.
+ * Renamed entries have empty path followed by old and new paths, binary files have "-" counts.
+ */
+pub fn parse_numstat(output: &str) -> HashMap<String, LineStats> {
+    let mut result: HashMap<String, LineStats> = HashMap::new();
+    let mut parts = output.split('\0');
+    while let Some(entry) = parts.next() {
+        let mut columns = entry.splitn(3, '\t');
+        let (added, deleted, path) = match (columns.next(), columns.next(), columns.next()) {
+            (Some(added), Some(deleted), Some(path)) => (added, deleted, path),
+            // it is for latest line
+            _ => continue,
+        };
+        let path = if path.is_empty() {
+            // skip old path of renamed file
+            parts.next();
+            parts.next().unwrap_or_default()
+        } else {
+            path
+        };
+        let binary = added == "-" && deleted == "-";
+        result.insert(
+            path.to_string(),
+            LineStats {
+                added_lines: added.parse().unwrap_or(0),
+                deleted_lines: deleted.parse().unwrap_or(0),
+                binary,
+            },
+        );
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = r"* @org/core
+/payments/ @org/payments
+";
+
+    const HEAD: &str = r"* @org/core
+/payments/ @org/payments
+/billing/ @org/billing
+";
+
+    #[test]
+    fn parses_name_status() {
+        let output =
+            "M\0src/main.rs\0A\0new.rs\0D\0old.rs\0R087\0a/x.rs\0b/x.rs\0C100\0c.rs\0d.rs\0";
+        assert_eq!(
+            parse_name_status(output),
+            vec![
+                ChangedFile {
+                    status: ChangeStatus::Modified,
+                    path: "src/main.rs".into(),
+                    old_path: None,
+                    lines: LineStats::default(),
+                },
+                ChangedFile {
+                    status: ChangeStatus::Added,
+                    path: "new.rs".into(),
+                    old_path: None,
+                    lines: LineStats::default(),
+                },
+                ChangedFile {
+                    status: ChangeStatus::Deleted,
+                    path: "old.rs".into(),
+                    old_path: None,
+                    lines: LineStats::default(),
+                },
+                ChangedFile {
+                    status: ChangeStatus::Renamed,
+                    path: "b/x.rs".into(),
+                    old_path: Some("a/x.rs".into()),
+                    lines: LineStats::default(),
+                },
+                ChangedFile {
+                    status: ChangeStatus::Copied,
+                    path: "d.rs".into(),
+                    old_path: Some("c.rs".into()),
+                    lines: LineStats::default(),
+                },
+            ]
+        )
+    }
+
+    #[test]
+    fn deleted_file_uses_base_codeowners() {
+        let base = codeowners::from_reader(BASE.as_bytes());
+        let head = codeowners::from_reader("* @org/core".as_bytes());
+        let changes = parse_name_status("D\0payments/api.rs\0");
+        let groups = group_changes_by_owners(&changes, &base, &head, OwnersGrouping::Set);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].owners, vec![Owner::Team("@org/payments".into())]);
+    }
+
+    #[test]
+    fn renamed_file_listed_under_old_and_new_owners() {
+        let base = codeowners::from_reader(BASE.as_bytes());
+        let head = codeowners::from_reader(HEAD.as_bytes());
+        let changes = parse_name_status("R100\0payments/api.rs\0billing/api.rs\0");
+        let groups = group_changes_by_owners(&changes, &base, &head, OwnersGrouping::Set);
+        assert_eq!(
+            groups
+                .iter()
+                .map(|group| group.label.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["@org/billing", "@org/payments"]
+        );
+        for group in groups {
+            assert_eq!(group.files[0].change.path, "billing/api.rs");
+            assert_eq!(
+                group.files[0].old_owners,
+                Some(vec![Owner::Team("@org/payments".into())])
+            );
+        }
+    }
+
+    #[test]
+    fn renamed_from_unowned_into_owned_is_not_unowned() {
+        let base = codeowners::from_reader("/payments/ @org/payments\n".as_bytes());
+        let changes = parse_name_status("R100\0scratch/api.rs\0payments/api.rs\0");
+        let groups = group_changes_by_owners(&changes, &base, &base, OwnersGrouping::Set);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].owners, vec![Owner::Team("@org/payments".into())]);
+        assert_eq!(groups[0].files[0].old_owners, Some(vec![]));
+    }
+
+    #[test]
+    fn ownership_changes_lists_only_differing_files() {
+        let base = codeowners::from_reader(BASE.as_bytes());
+        let head = codeowners::from_reader(HEAD.as_bytes());
+        let files = vec![
+            String::from("README.md"),
+            String::from("payments/api.rs"),
+            String::from("billing/api.rs"),
+        ];
+        assert_eq!(
+            get_ownership_changes(&files, &base, &head),
+            vec![OwnershipChange {
+                path: "billing/api.rs".into(),
+                base_owners: vec![Owner::Team("@org/core".into())],
+                head_owners: vec![Owner::Team("@org/billing".into())],
+            }]
+        );
+    }
+
+    #[test]
+    fn parses_numstat() {
+        let output = "1\t0\tCODEOWNERS\x005\t2\t\0payments/api.rs\0billing/api.rs\0-\t-\tbin.dat\0";
+        let stats = parse_numstat(output);
+        assert_eq!(stats.len(), 3);
+        assert_eq!(
+            stats["billing/api.rs"],
+            LineStats {
+                added_lines: 5,
+                deleted_lines: 2,
+                binary: false
+            }
+        );
+        assert!(stats["bin.dat"].binary);
+    }
+
+    #[test]
+    fn parses_raw_with_numstat() {
+        let output = ":100644 100644 f392a4f ca5de62 M\0CODEOWNERS\0\
+            :100644 100644 96cc558 96cc558 R100\0payments/api.rs\0billing/api.rs\0\
+            :100644 000000 587be6b 0000000 D\0gone.txt\0\
+            1\t0\tCODEOWNERS\x000\t0\t\0payments/api.rs\0billing/api.rs\x000\t1\tgone.txt\0";
+        let changes = parse_raw_numstat(output);
+        assert_eq!(
+            changes
+                .iter()
+                .map(|change| (
+                    change.status,
+                    change.path.as_str(),
+                    change.lines.added_lines
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (ChangeStatus::Modified, "CODEOWNERS", 1),
+                (ChangeStatus::Renamed, "billing/api.rs", 0),
+                (ChangeStatus::Deleted, "gone.txt", 0),
+            ]
+        );
+        assert_eq!(changes[1].old_path.as_deref(), Some("payments/api.rs"));
+        assert_eq!(changes[2].lines.deleted_lines, 1);
+        assert!(parse_raw_numstat("").is_empty());
+    }
+
+    #[test]
+    fn group_sums_changed_lines() {
+        let head = codeowners::from_reader(HEAD.as_bytes());
+        let mut changes = parse_name_status("M\0payments/a.rs\0M\0payments/b.rs\0");
+        changes[0].lines.added_lines = 10;
+        changes[1].lines.added_lines = 4;
+        changes[1].lines.deleted_lines = 3;
+        let groups = group_changes_by_owners(&changes, &head, &head, OwnersGrouping::Set);
+        assert_eq!(groups[0].added_lines, 14);
+        assert_eq!(groups[0].deleted_lines, 3);
+    }
+
+    #[test]
+    fn owner_order_does_not_split_groups() {
+        let codeowners = codeowners::from_reader("/a/ @org/x @bob\n/b/ @bob @org/x\n".as_bytes());
+        let changes = parse_name_status("M\0a/1.rs\0M\0b/2.rs\0");
+        let groups =
+            group_changes_by_owners(&changes, &codeowners, &codeowners, OwnersGrouping::Set);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].label, "@bob, @org/x");
+        assert_eq!(groups[0].files.len(), 2);
+    }
+
+    #[test]
+    fn individual_grouping_lists_file_under_every_owner() {
+        let codeowners = codeowners::from_reader("/a/ @org/x @bob\n/b/ @bob\n".as_bytes());
+        let changes = parse_name_status("M\0a/1.rs\0M\0b/2.rs\0A\0c.rs\0");
+        let groups = group_changes_by_owners(
+            &changes,
+            &codeowners,
+            &codeowners,
+            OwnersGrouping::Individual,
+        );
+        assert_eq!(
+            groups
+                .iter()
+                .map(|group| (group.label.as_str(), group.files.len()))
+                .collect::<Vec<_>>(),
+            vec![("", 1), ("@bob", 2), ("@org/x", 1)]
+        );
+    }
+}
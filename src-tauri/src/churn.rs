@@ -0,0 +1,236 @@
+//! Commits and changed lines per owner group and per directory over recent history, data of a heatmap
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::all_owners::{self, DirectoryOwners};
+use crate::git::{self, CommitChanges};
+use crate::join_owners;
+use codeowners::Owner;
+
+/** History window used by default */
+pub const DEFAULT_MONTHS: u32 = 3;
+
+#[derive(Serialize, Clone, Debug, PartialEq, Default)]
+pub struct Churn {
+    /** Commits are counted once even if they change several files */
+    pub commits: usize,
+    /** Added and deleted lines, a change of a binary file counts as one line */
+    pub lines: u64,
+    #[serde(skip)]
+    last_commit: Option<usize>,
+}
+
+impl Churn {
+    fn add(&mut self, commit: usize, lines: u64) {
+        if self.last_commit != Some(commit) {
+            self.last_commit = Some(commit);
+            self.commits += 1;
+        }
+        self.lines += lines;
+    }
+}
+
+/** Files with the same set of owners */
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct OwnerGroupChurn {
+    /** Owners joined with ", " like in the tree. Empty for unowned files */
+    pub owner: String,
+    pub owners: Vec<Owner>,
+    #[serde(flatten)]
+    pub churn: Churn,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct DirectoryChurn {
+    /** Path without trailing slash */
+    pub path: String,
+    /** 1 for top-level directories */
+    pub depth: usize,
+    #[serde(flatten)]
+    pub churn: Churn,
+    /** Cells of the heatmap row, in order of owner groups of the report */
+    pub owners: Vec<Churn>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ChurnReport {
+    pub branch: String,
+    pub months: u32,
+    pub total: Churn,
+    /** Owner groups which had changes, the most changed lines first */
+    pub owners: Vec<OwnerGroupChurn>,
+    /** Directories down to the requested depth, parents before children */
+    pub directories: Vec<DirectoryChurn>,
+}
+
+/** Churn of the last `months` of files of the branch, owners are resolved like in the all-owners tree */
+pub fn get_churn_report(
+    abs_repo_path: &str,
+    branch: &str,
+    months: u32,
+    depth: usize,
+) -> ChurnReport {
+    let root = all_owners::get_all_codeowners_for_branch_struct(abs_repo_path, branch, |_, _| {});
+    let history = git::get_commit_changes(abs_repo_path, branch, &format!("{months} months ago"));
+    churn_report(branch, months, &root, &history, depth)
+}
+
+/** Files which don't exist in the branch anymore are skipped, they have no owners to blame */
+pub fn churn_report(
+    branch: &str,
+    months: u32,
+    root: &DirectoryOwners,
+    history: &[CommitChanges],
+    depth: usize,
+) -> ChurnReport {
+    let owners_by_path = root.owners_by_path();
+    let directory_paths = root.directory_paths(depth);
+    let mut total = Churn::default();
+    let mut groups: BTreeMap<&Vec<Owner>, Churn> = BTreeMap::new();
+    let mut directories: HashMap<&str, Churn> = HashMap::new();
+    // directory -> owner group -> churn
+    let mut cells: HashMap<&str, BTreeMap<&Vec<Owner>, Churn>> = HashMap::new();
+    for (index, changes) in history.iter().enumerate() {
+        for file in &changes.files {
+            let owners = match owners_by_path.get(&file.path) {
+                Some(owners) => *owners,
+                None => continue,
+            };
+            let lines = file.changed_lines();
+            total.add(index, lines);
+            groups.entry(owners).or_default().add(index, lines);
+            for directory in all_owners::parent_directories(&file.path, depth) {
+                directories.entry(directory).or_default().add(index, lines);
+                cells
+                    .entry(directory)
+                    .or_default()
+                    .entry(owners)
+                    .or_default()
+                    .add(index, lines);
+            }
+        }
+    }
+
+    let mut groups: Vec<(&Vec<Owner>, Churn)> = groups.into_iter().collect();
+    groups.sort_by_key(|(_, churn)| Reverse((churn.lines, churn.commits)));
+    let directories = directory_paths
+        .iter()
+        .map(|path| {
+            let row = cells.remove(path.as_str()).unwrap_or_default();
+            DirectoryChurn {
+                path: path.clone(),
+                depth: path.matches('/').count() + 1,
+                churn: directories.remove(path.as_str()).unwrap_or_default(),
+                owners: groups
+                    .iter()
+                    .map(|(owners, _)| row.get(owners).cloned().unwrap_or_default())
+                    .collect(),
+            }
+        })
+        .collect();
+    ChurnReport {
+        branch: branch.to_string(),
+        months,
+        total,
+        owners: groups
+            .into_iter()
+            .map(|(owners, churn)| OwnerGroupChurn {
+                owner: join_owners(owners),
+                owners: owners.clone(),
+                churn,
+            })
+            .collect(),
+        directories,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::all_owners::FileOwners;
+    use crate::git::{Commit, FileChange};
+
+    fn commit(files: Vec<(&str, u32)>) -> CommitChanges {
+        CommitChanges {
+            commit: Commit {
+                sha: String::from("abc"),
+                author_name: String::from("Someone"),
+                author_email: String::from("someone@example.com"),
+                timestamp: 0,
+                subject: String::new(),
+            },
+            files: files
+                .into_iter()
+                .map(|(path, lines)| FileChange {
+                    path: path.into(),
+                    added_lines: lines,
+                    deleted_lines: 1,
+                })
+                .collect(),
+        }
+    }
+
+    fn directory(name: &str, files: Vec<(&str, Vec<Owner>)>) -> DirectoryOwners {
+        DirectoryOwners {
+            name: name.into(),
+            directories: vec![],
+            files: files
+                .into_iter()
+                .map(|(name, owners)| FileOwners {
+                    name: name.into(),
+                    owner: join_owners(&owners),
+                    owners,
+                })
+                .collect(),
+            owner: None,
+        }
+    }
+
+    #[test]
+    fn counts_churn_per_owner_group_and_directory() {
+        let core = Owner::Team("@org/core".into());
+        let alice = Owner::Username("@alice".into());
+        let mut root = directory("", vec![("README.md", vec![])]);
+        root.directories = vec![
+            directory(
+                "src",
+                vec![
+                    ("a.rs", vec![core.clone()]),
+                    ("b.rs", vec![alice.clone(), core.clone()]),
+                ],
+            ),
+            directory("docs", vec![("x.md", vec![alice.clone()])]),
+        ];
+        let history = vec![
+            commit(vec![("src/a.rs", 9), ("src/b.rs", 4), ("docs/x.md", 1)]),
+            commit(vec![("src/a.rs", 19), ("gone.rs", 100)]),
+            commit(vec![("README.md", 0)]),
+        ];
+        let report = churn_report("main", 3, &root, &history, 1);
+        assert_eq!((report.total.commits, report.total.lines), (3, 38));
+        assert_eq!(
+            report
+                .owners
+                .iter()
+                .map(|group| (group.owner.as_str(), group.churn.commits, group.churn.lines))
+                .collect::<Vec<_>>(),
+            vec![
+                ("@org/core", 2, 30),
+                ("@alice, @org/core", 1, 5),
+                ("@alice", 1, 2),
+                ("", 1, 1)
+            ]
+        );
+        let src = &report.directories[0];
+        assert_eq!(
+            (src.path.as_str(), src.churn.commits, src.churn.lines),
+            ("src", 2, 35)
+        );
+        assert_eq!(
+            src.owners.iter().map(|cell| cell.lines).collect::<Vec<_>>(),
+            vec![30, 5, 0, 0]
+        );
+        assert_eq!(report.directories[1].churn.lines, 2);
+    }
+}
@@ -0,0 +1,140 @@
+//! Ownership changes between CODEOWNERS files of two revisions
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::git;
+use crate::normalize_owners;
+use codeowners::{Owner, Owners, Rule};
+
+/** CODEOWNERS line which determined owners of a file */
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct RuleRef {
+    pub line: usize,
+    pub pattern: String,
+}
+
+impl From<&Rule> for RuleRef {
+    fn from(rule: &Rule) -> Self {
+        RuleRef {
+            line: rule.line,
+            pattern: rule.raw_pattern.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct TransitionFile {
+    pub path: String,
+    /** Rule of the old CODEOWNERS. Null if file wasn't owned */
+    pub old_rule: Option<RuleRef>,
+    /** Rule of the new CODEOWNERS. Null if file isn't owned anymore */
+    pub new_rule: Option<RuleRef>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct OwnershipTransition {
+    pub old_owners: Vec<Owner>,
+    pub new_owners: Vec<Owner>,
+    pub files: Vec<TransitionFile>,
+}
+
+/**
+ * Resolves every file of `to_revision` tree with CODEOWNERS of both revisions.
+ * Returns files whose owners changed grouped by old owners -> new owners.
+ * Fails if any of revisions doesn't exist, so a typo isn't shown as "nothing changed"
+ */
+pub fn get_codeowners_diff(
+    abs_repo_path: &str,
+    from_revision: &str,
+    to_revision: &str,
+) -> Result<Vec<OwnershipTransition>, String> {
+    let from_commit = git::resolve_commit(abs_repo_path, from_revision)?;
+    let to_commit = git::resolve_commit(abs_repo_path, to_revision)?;
+    // missing CODEOWNERS is not an error, all files are unowned then
+    let old_content = git::get_codeowners_content(abs_repo_path, &from_commit);
+    let old_codeowners = codeowners::from_reader(old_content.as_bytes());
+    let new_content = git::get_codeowners_content(abs_repo_path, &to_commit);
+    let new_codeowners = codeowners::from_reader(new_content.as_bytes());
+    let files = git::get_branch_files_vector(abs_repo_path, &to_commit);
+    Ok(group_ownership_transitions(
+        &files,
+        &old_codeowners,
+        &new_codeowners,
+    ))
+}
+
+pub fn group_ownership_transitions(
+    files: &[String],
+    old_codeowners: &Owners,
+    new_codeowners: &Owners,
+) -> Vec<OwnershipTransition> {
+    // BTreeMap keeps groups sorted, we have to send stable data
+    let mut transitions: BTreeMap<(Vec<Owner>, Vec<Owner>), Vec<TransitionFile>> = BTreeMap::new();
+    for file_path in files {
+        let old_rule = old_codeowners.rule_of(file_path);
+        let new_rule = new_codeowners.rule_of(file_path);
+        let old_owners = normalize_owners(old_rule.map(|rule| &rule.owners));
+        let new_owners = normalize_owners(new_rule.map(|rule| &rule.owners));
+        if old_owners == new_owners {
+            continue;
+        }
+        transitions
+            .entry((old_owners, new_owners))
+            .or_default()
+            .push(TransitionFile {
+                path: file_path.to_string(),
+                old_rule: old_rule.map(RuleRef::from),
+                new_rule: new_rule.map(RuleRef::from),
+            });
+    }
+    transitions
+        .into_iter()
+        .map(|((old_owners, new_owners), files)| OwnershipTransition {
+            old_owners,
+            new_owners,
+            files,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_changed_files_by_transition() {
+        let old = codeowners::from_reader("* @org/core\n".as_bytes());
+        let new = codeowners::from_reader(
+            "* @org/core\n/payments/ @org/payments\n/billing/ @org/payments\n".as_bytes(),
+        );
+        let files = vec![
+            String::from("README.md"),
+            String::from("payments/api.rs"),
+            String::from("billing/api.rs"),
+        ];
+        let transitions = group_ownership_transitions(&files, &old, &new);
+        assert_eq!(transitions.len(), 1);
+        assert_eq!(
+            transitions[0].old_owners,
+            vec![Owner::Team("@org/core".into())]
+        );
+        assert_eq!(
+            transitions[0].new_owners,
+            vec![Owner::Team("@org/payments".into())]
+        );
+        assert_eq!(
+            transitions[0].files[1],
+            TransitionFile {
+                path: "billing/api.rs".into(),
+                old_rule: Some(RuleRef {
+                    line: 1,
+                    pattern: "*".into()
+                }),
+                new_rule: Some(RuleRef {
+                    line: 3,
+                    pattern: "/billing/".into()
+                }),
+            }
+        );
+    }
+}
@@ -0,0 +1,362 @@
+//! Share of files, bytes and lines of a branch which have owners, per directory
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use crate::all_owners::{self, DirectoryOwners};
+use crate::git;
+use crate::rounded_percent;
+use crate::statistics::{self, FileStats};
+use crate::template;
+
+/** Depth of directories listed by default, 1 means top-level directories only */
+pub const DEFAULT_DEPTH: usize = 2;
+/** Count of the largest unowned subtrees listed by default */
+pub const DEFAULT_TOP: usize = 10;
+
+const MARKDOWN_TEMPLATE: &str = r"# Ownership coverage of {{branch}}
+
+Owned files: **{{total.files.percent}}%**, bytes: **{{total.bytes.percent}}%**, lines: **{{total.lines.percent}}%**
+
+| Directory | Files | Bytes | Lines |
+|---|---|---|---|
+{{#directories}}
+| `{{path}}` | {{files.percent}}% ({{files.owned}} / {{files.total}}) | {{bytes.percent}}% | {{lines.percent}}% ({{lines.owned}} / {{lines.total}}) |
+{{/directories}}
+{{#largest_unowned}}
+{{#first}}
+
+## Largest unowned subtrees
+
+| Path | Files | Bytes | Lines |
+|---|---|---|---|
+{{/first}}
+| `{{path}}` | {{files}} | {{bytes}} | {{lines}} |
+{{/largest_unowned}}
+";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+pub struct CoverageCount {
+    pub owned: u64,
+    pub total: u64,
+    /** Owned share rounded to one decimal. 100 for empty directories */
+    pub percent: f64,
+}
+
+impl CoverageCount {
+    pub fn add(&mut self, value: u64, owned: bool) {
+        self.merge(CoverageCount {
+            owned: if owned { value } else { 0 },
+            total: value,
+            percent: 0.0,
+        });
+    }
+
+    fn merge(&mut self, other: CoverageCount) {
+        self.owned += other.owned;
+        self.total += other.total;
+        self.percent = rounded_percent(self.owned, self.total).unwrap_or(100.0);
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Default)]
+pub struct DirectoryCoverage {
+    /** Path without trailing slash, empty for the root */
+    pub path: String,
+    /** 0 for the root, 1 for top-level directories */
+    pub depth: usize,
+    pub files: CoverageCount,
+    pub bytes: CoverageCount,
+    /** Binary files are not counted in lines */
+    pub lines: CoverageCount,
+}
+
+impl DirectoryCoverage {
+    fn add(&mut self, file: &FileStats, owned: bool) {
+        self.files.add(1, owned);
+        self.bytes.add(file.bytes, owned);
+        self.lines.add(file.lines.unwrap_or_default(), owned);
+    }
+
+    fn is_unowned(&self) -> bool {
+        self.files.total > 0 && self.files.owned == 0
+    }
+}
+
+/**
+ * Directory whose every file is unowned while its parent has owned files, or a single unowned file.
+ * `/` means the whole repository is unowned
+ */
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct UnownedSubtree {
+    /** Directory paths end with a slash */
+    pub path: String,
+    pub files: u64,
+    pub bytes: u64,
+    pub lines: u64,
+    /** True for the first item of the list, lets templates render list headers once */
+    pub first: bool,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct CoverageReport {
+    pub branch: String,
+    pub total: DirectoryCoverage,
+    /** Directories up to the requested depth, parents before children */
+    pub directories: Vec<DirectoryCoverage>,
+    /** Sorted by lines, then by bytes, the largest first */
+    pub largest_unowned: Vec<UnownedSubtree>,
+}
+
+/**
+ * Coverage of the branch per directory down to `depth`.
+ * Sizes and lines are taken from statistics cached per tree.
+ */
+pub fn get_coverage_report(
+    abs_repo_path: &str,
+    branch: &str,
+    depth: usize,
+    top: usize,
+) -> CoverageReport {
+    let root = all_owners::get_all_codeowners_for_branch_struct(abs_repo_path, branch, |_, _| {});
+    let tree = git::get_tree_sha(abs_repo_path, branch);
+    let files = statistics::get_tree_file_stats(abs_repo_path, &tree);
+    coverage_report(branch, &root, &files, depth, top)
+}
+
+pub fn coverage_report(
+    branch: &str,
+    root: &DirectoryOwners,
+    files: &[FileStats],
+    depth: usize,
+    top: usize,
+) -> CoverageReport {
+    let files_by_path: HashMap<&str, &FileStats> = files
+        .iter()
+        .map(|file| (file.path.as_str(), file))
+        .collect();
+    let mut directories: Vec<DirectoryCoverage> = Vec::new();
+    let mut unowned: Vec<UnownedSubtree> = Vec::new();
+    let total = walk(
+        root,
+        "",
+        0,
+        depth,
+        &files_by_path,
+        &mut directories,
+        &mut unowned,
+    );
+    if total.is_unowned() {
+        unowned = vec![subtree("/", &total)];
+    }
+    unowned.sort_by_key(|subtree| Reverse((subtree.lines, subtree.bytes)));
+    unowned.truncate(top);
+    if let Some(first) = unowned.first_mut() {
+        first.first = true;
+    }
+    CoverageReport {
+        branch: branch.to_string(),
+        total,
+        directories,
+        largest_unowned: unowned,
+    }
+}
+
+fn subtree(path: &str, coverage: &DirectoryCoverage) -> UnownedSubtree {
+    UnownedSubtree {
+        path: path.to_string(),
+        files: coverage.files.total,
+        bytes: coverage.bytes.total,
+        lines: coverage.lines.total,
+        first: false,
+    }
+}
+
+fn join_path(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{parent}/{name}")
+    }
+}
+
+/** Returns coverage of the directory. Children are listed before it is known, so they are inserted after it */
+fn walk(
+    directory: &DirectoryOwners,
+    path: &str,
+    depth: usize,
+    max_depth: usize,
+    files_by_path: &HashMap<&str, &FileStats>,
+    directories: &mut Vec<DirectoryCoverage>,
+    unowned: &mut Vec<UnownedSubtree>,
+) -> DirectoryCoverage {
+    let mut coverage = DirectoryCoverage {
+        path: path.to_string(),
+        depth,
+        ..DirectoryCoverage::default()
+    };
+    let position = directories.len();
+    let mut unowned_children: Vec<UnownedSubtree> = Vec::new();
+    for file in &directory.files {
+        let file_path = join_path(path, &file.name);
+        let stats = files_by_path
+            .get(file_path.as_str())
+            .copied()
+            .cloned()
+            .unwrap_or(FileStats {
+                path: file_path.clone(),
+                bytes: 0,
+                lines: Some(0),
+            });
+        let owned = !file.owners.is_empty();
+        coverage.add(&stats, owned);
+        if !owned {
+            unowned_children.push(UnownedSubtree {
+                path: file_path,
+                files: 1,
+                bytes: stats.bytes,
+                lines: stats.lines.unwrap_or_default(),
+                first: false,
+            });
+        }
+    }
+    for child in &directory.directories {
+        let child_path = join_path(path, &child.name);
+        let child_coverage = walk(
+            child,
+            &child_path,
+            depth + 1,
+            max_depth,
+            files_by_path,
+            directories,
+            &mut unowned_children,
+        );
+        coverage.files.merge(child_coverage.files);
+        coverage.bytes.merge(child_coverage.bytes);
+        coverage.lines.merge(child_coverage.lines);
+        if child_coverage.is_unowned() {
+            unowned_children.push(subtree(&format!("{child_path}/"), &child_coverage));
+        }
+    }
+    // the whole directory is reported instead of its parts by the parent
+    if !coverage.is_unowned() {
+        unowned.append(&mut unowned_children);
+    }
+    if depth > 0 && depth <= max_depth {
+        directories.insert(position, coverage.clone());
+    }
+    coverage
+}
+
+/**
+ * Paths of all unowned subtrees, the same ones `largest_unowned` lists but without its limit.
+ * Sizes are not read, so paths stay in walk order
+ */
+pub fn unowned_paths(root: &DirectoryOwners) -> Vec<String> {
+    coverage_report("", root, &[], 0, usize::MAX)
+        .largest_unowned
+        .into_iter()
+        .map(|subtree| subtree.path)
+        .collect()
+}
+
+/** Report as Markdown with a table of directories and a table of the largest unowned subtrees */
+pub fn coverage_markdown(report: &CoverageReport) -> String {
+    template::render(MARKDOWN_TEMPLATE, &serde_json::to_value(report).unwrap())
+        .expect("built-in template is valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::all_owners::FileOwners;
+
+    fn directory(
+        name: &str,
+        files: Vec<(&str, bool)>,
+        directories: Vec<DirectoryOwners>,
+    ) -> DirectoryOwners {
+        DirectoryOwners {
+            name: name.into(),
+            directories,
+            files: files
+                .into_iter()
+                .map(|(name, owned)| FileOwners {
+                    name: name.into(),
+                    owner: if owned {
+                        "@org/core".into()
+                    } else {
+                        String::new()
+                    },
+                    owners: if owned {
+                        vec![codeowners::Owner::Team("@org/core".into())]
+                    } else {
+                        vec![]
+                    },
+                })
+                .collect(),
+            owner: None,
+        }
+    }
+
+    fn stats(path: &str, lines: u64) -> FileStats {
+        FileStats {
+            path: path.into(),
+            bytes: lines * 10,
+            lines: Some(lines),
+        }
+    }
+
+    #[test]
+    fn reports_coverage_per_directory() {
+        let root = directory(
+            "",
+            vec![("README.md", false)],
+            vec![
+                directory(
+                    "src",
+                    vec![("main.rs", true)],
+                    vec![directory(
+                        "gen",
+                        vec![("a.rs", false), ("b.rs", false)],
+                        vec![],
+                    )],
+                ),
+                directory("docs", vec![("x.md", false)], vec![]),
+            ],
+        );
+        let files = vec![
+            stats("README.md", 5),
+            stats("src/main.rs", 30),
+            stats("src/gen/a.rs", 50),
+            stats("src/gen/b.rs", 10),
+            stats("docs/x.md", 20),
+        ];
+        let report = coverage_report("main", &root, &files, 1, 2);
+        assert_eq!(report.total.files.owned, 1);
+        assert_eq!(report.total.files.total, 5);
+        assert_eq!(report.total.lines.percent, 26.1);
+        let mut count = CoverageCount::default();
+        count.add(2, true);
+        count.add(1, false);
+        assert_eq!(count.percent, 66.7);
+        assert_eq!(
+            report
+                .directories
+                .iter()
+                .map(|directory| (directory.path.as_str(), directory.lines.percent))
+                .collect::<Vec<_>>(),
+            vec![("src", 33.3), ("docs", 0.0)]
+        );
+        assert_eq!(
+            report
+                .largest_unowned
+                .iter()
+                .map(|subtree| (subtree.path.as_str(), subtree.lines))
+                .collect::<Vec<_>>(),
+            vec![("src/gen/", 60), ("docs/", 20)]
+        );
+        assert_eq!(unowned_paths(&root), vec!["README.md", "src/gen/", "docs/"]);
+        assert!(coverage_markdown(&report).contains("| `src/gen/` | 2 | 600 | 60 |"));
+    }
+}
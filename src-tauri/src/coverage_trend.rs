@@ -0,0 +1,212 @@
+//! Ownership coverage sampled across history of a branch, e.g. one commit per week of main
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+
+use crate::coverage::CoverageCount;
+use crate::git;
+use crate::normalize_owners;
+use crate::statistics::{self, FileStats, CACHE_DIRECTORY};
+use codeowners::{Owner, Owners};
+
+/** Sampling interval used by default */
+pub const DEFAULT_INTERVAL_DAYS: u32 = 7;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/** Coverage of a whole tree. It depends on the tree only, so it is cached per tree SHA */
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct TreeCoverage {
+    pub files: CoverageCount,
+    pub bytes: CoverageCount,
+    /** Binary files are not counted in lines */
+    pub lines: CoverageCount,
+    /** Count of distinct owners which own at least one file */
+    pub owners: usize,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct TrendPoint {
+    pub commit: String,
+    /** Committer date as `YYYY-MM-DD` */
+    pub date: String,
+    /** Committer date as unix timestamp in seconds */
+    pub timestamp: i64,
+    #[serde(flatten)]
+    pub coverage: TreeCoverage,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct CoverageTrend {
+    pub range: String,
+    pub interval_days: u32,
+    /** Oldest first */
+    pub points: Vec<TrendPoint>,
+}
+
+/** First-parent commit of the sampled history */
+#[derive(Clone, Debug, PartialEq)]
+pub struct HistoryCommit {
+    pub sha: String,
+    pub timestamp: i64,
+    pub date: String,
+}
+
+/**
+ * Coverage of commits of the range sampled not more often than once per `interval_days`.
+ * Only first parents are followed, so merged branches don't add their own commits.
+ * The oldest and the newest commits of the range are always sampled.
+ * Fails if git can't read the range, e.g. one of its revisions doesn't exist
+ */
+pub fn get_coverage_trend(
+    abs_repo_path: &str,
+    range: &str,
+    interval_days: u32,
+) -> Result<CoverageTrend, String> {
+    let output = git::try_run_git(
+        abs_repo_path,
+        &[
+            "log",
+            "--first-parent",
+            "--reverse",
+            "--format=%H%x1f%ct%x1f%cs",
+            range,
+        ],
+    )?;
+    let commits = parse_history(&output);
+    let points = sample_commits(&commits, interval_days)
+        .into_iter()
+        .map(|commit| TrendPoint {
+            commit: commit.sha.clone(),
+            date: commit.date.clone(),
+            timestamp: commit.timestamp,
+            coverage: get_tree_coverage(abs_repo_path, &commit.sha),
+        })
+        .collect();
+    Ok(CoverageTrend {
+        range: range.to_string(),
+        interval_days,
+        points,
+    })
+}
+
+pub fn parse_history(output: &str) -> Vec<HistoryCommit> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\x1f');
+            Some(HistoryCommit {
+                sha: fields.next().filter(|sha| !sha.is_empty())?.to_string(),
+                timestamp: fields.next()?.parse().ok()?,
+                date: fields.next().unwrap_or_default().to_string(),
+            })
+        })
+        .collect()
+}
+
+/** Takes a commit when at least the interval passed since the previously taken one. Commits are oldest first */
+pub fn sample_commits(commits: &[HistoryCommit], interval_days: u32) -> Vec<&HistoryCommit> {
+    let interval = i64::from(interval_days) * SECONDS_PER_DAY;
+    let mut samples: Vec<&HistoryCommit> = Vec::new();
+    for commit in commits {
+        match samples.last() {
+            Some(last) if commit.timestamp - last.timestamp < interval => {}
+            _ => samples.push(commit),
+        }
+    }
+    if let Some(newest) = commits.last() {
+        if samples.last().map(|last| &last.sha) != Some(&newest.sha) {
+            samples.push(newest);
+        }
+    }
+    samples
+}
+
+/** Coverage of the commit tree with its own CODEOWNERS, read from the cache if the tree was seen before */
+pub fn get_tree_coverage(abs_repo_path: &str, revision: &str) -> TreeCoverage {
+    let tree = git::get_tree_sha(abs_repo_path, revision);
+    if tree.is_empty() {
+        return TreeCoverage::default();
+    }
+    let cache_path = git::get_git_path(
+        abs_repo_path,
+        &format!("{CACHE_DIRECTORY}/coverage-{tree}.json"),
+    );
+    if let Some(coverage) = fs::read_to_string(&cache_path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+    {
+        return coverage;
+    }
+    let files = statistics::get_tree_file_stats(abs_repo_path, &tree);
+    let codeowners_content = git::get_codeowners_content(abs_repo_path, revision);
+    let coverage = tree_coverage(
+        &files,
+        &codeowners::from_reader(codeowners_content.as_bytes()),
+    );
+    statistics::write_cache(&cache_path, &serde_json::to_string(&coverage).unwrap());
+    coverage
+}
+
+pub fn tree_coverage(files: &[FileStats], codeowners: &Owners) -> TreeCoverage {
+    let mut coverage = TreeCoverage::default();
+    let mut owners: BTreeSet<Owner> = BTreeSet::new();
+    for file in files {
+        let file_owners = normalize_owners(codeowners.of(&file.path));
+        let owned = !file_owners.is_empty();
+        coverage.files.add(1, owned);
+        coverage.bytes.add(file.bytes, owned);
+        coverage.lines.add(file.lines.unwrap_or_default(), owned);
+        owners.extend(file_owners);
+    }
+    coverage.owners = owners.len();
+    coverage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_once_per_interval() {
+        let output: String = [0, 1, 3, 8, 9, 16, 17]
+            .iter()
+            .map(|day| {
+                format!(
+                    "c{day}\x1f{}\x1f2024-01-{:02}\n",
+                    day * SECONDS_PER_DAY,
+                    day + 1
+                )
+            })
+            .collect();
+        let commits = parse_history(&output);
+        assert_eq!(commits[1].date, "2024-01-02");
+        let samples: Vec<&str> = sample_commits(&commits, 7)
+            .iter()
+            .map(|commit| commit.sha.as_str())
+            .collect();
+        assert_eq!(samples, vec!["c0", "c8", "c16", "c17"]);
+    }
+
+    #[test]
+    fn counts_coverage_and_owners() {
+        let codeowners =
+            codeowners::from_reader("/src/ @alice @org/core\n/docs/ @alice\n".as_bytes());
+        let file = |path: &str, lines: u64| FileStats {
+            path: path.into(),
+            bytes: lines,
+            lines: Some(lines),
+        };
+        let coverage = tree_coverage(
+            &[
+                file("src/a.rs", 30),
+                file("docs/b.md", 10),
+                file("c.txt", 10),
+            ],
+            &codeowners,
+        );
+        assert_eq!(coverage.files.owned, 2);
+        assert_eq!(coverage.lines.percent, 80.0);
+        assert_eq!(coverage.owners, 2);
+    }
+}
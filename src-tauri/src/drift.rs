@@ -0,0 +1,278 @@
+//! Declared owners of CODEOWNERS rules compared with people who actually change matching files
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use crate::git::{self, CommitChanges};
+use crate::identity::IdentityResolver;
+use crate::normalize_owners;
+use crate::roster::Roster;
+use codeowners::{Owner, Owners};
+
+/** History window used by default */
+pub const DEFAULT_MONTHS: u32 = 6;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct AuthorActivity {
+    /** Username if it is known, email otherwise */
+    pub author: String,
+    /** Commits which changed files of the rule */
+    pub commits: usize,
+    /** Added and deleted lines of files of the rule */
+    pub lines: u64,
+    /** True if the author is one of the rule owners directly or through roster */
+    pub owner: bool,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct RuleDrift {
+    /** Line of the rule in CODEOWNERS */
+    pub line: usize,
+    pub pattern: String,
+    pub owners: Vec<Owner>,
+    /** Commits which changed files whose owners this rule determines */
+    pub commits: usize,
+    /** Commits of them made by owners */
+    pub owners_commits: usize,
+    /** Declared owners who made no commits to files of the rule. Unresolved teams are not listed */
+    pub inactive_owners: Vec<Owner>,
+    /**
+     * Owner teams whose members the roster doesn't list. Their members can't be told
+     * from other authors, so such rule is never flagged as drifted
+     */
+    pub unresolved_teams: Vec<Owner>,
+    /** Authors of the commits, the most commits first */
+    pub authors: Vec<AuthorActivity>,
+    /** Files of the rule were changed, but never by its owners */
+    pub owners_never_commit: bool,
+    /** Author who is not an owner and made more than half of the commits */
+    pub dominant_non_owner: Option<AuthorActivity>,
+}
+
+impl RuleDrift {
+    pub fn drifted(&self) -> bool {
+        self.owners_never_commit || self.dominant_non_owner.is_some()
+    }
+
+    /** Files of the rule were changed, but it is unknown whether by owners */
+    pub fn unknown(&self) -> bool {
+        self.commits > 0 && !self.unresolved_teams.is_empty()
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct DriftReport {
+    pub branch: String,
+    pub months: u32,
+    /** Rules with owners in order of CODEOWNERS lines */
+    pub rules: Vec<RuleDrift>,
+}
+
+/** Compares owners of every rule of the branch CODEOWNERS with authors of the last `months` */
+pub fn get_ownership_drift(
+    abs_repo_path: &str,
+    branch: &str,
+    months: u32,
+    roster: &Roster,
+) -> DriftReport {
+    let content = git::get_codeowners_content(abs_repo_path, branch);
+    let codeowners = codeowners::from_reader(content.as_bytes());
+    let history = git::get_commit_changes(abs_repo_path, branch, &format!("{months} months ago"));
+    let identities = IdentityResolver::load(abs_repo_path, roster.clone());
+    DriftReport {
+        branch: branch.to_string(),
+        months,
+        rules: ownership_drift(&codeowners, &history, &identities, roster),
+    }
+}
+
+/**
+ * Every changed file is attributed to the rule which determines its owners now,
+ * so files changed before the rule was added count too
+ */
+pub fn ownership_drift(
+    codeowners: &Owners,
+    history: &[CommitChanges],
+    identities: &IdentityResolver,
+    roster: &Roster,
+) -> Vec<RuleDrift> {
+    // activity per rule line: author -> (commits, lines)
+    let mut activity: BTreeMap<usize, BTreeMap<String, (usize, u64)>> = BTreeMap::new();
+    for changes in history {
+        let identity =
+            identities.resolve(&changes.commit.author_name, &changes.commit.author_email);
+        let author = identity.username.unwrap_or(identity.email);
+        let mut touched: Vec<usize> = Vec::new();
+        for file in &changes.files {
+            let rule = match codeowners.rule_of(&file.path) {
+                Some(rule) if !rule.owners.is_empty() => rule,
+                _ => continue,
+            };
+            let entry = activity
+                .entry(rule.line)
+                .or_default()
+                .entry(author.clone())
+                .or_default();
+            entry.1 += u64::from(file.added_lines) + u64::from(file.deleted_lines);
+            if !touched.contains(&rule.line) {
+                touched.push(rule.line);
+                entry.0 += 1;
+            }
+        }
+    }
+
+    let mut rules: Vec<RuleDrift> = codeowners
+        .rules()
+        .iter()
+        .filter(|rule| !rule.owners.is_empty())
+        .map(|rule| {
+            let owners = normalize_owners(Some(&rule.owners));
+            let mut authors: Vec<AuthorActivity> = activity
+                .remove(&rule.line)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(author, (commits, lines))| AuthorActivity {
+                    owner: owners.iter().any(|owner| roster.is_owner(owner, &author)),
+                    author,
+                    commits,
+                    lines,
+                })
+                .collect();
+            authors.sort_by_key(|author| Reverse((author.commits, author.lines)));
+            let commits: usize = authors.iter().map(|author| author.commits).sum();
+            let owners_commits: usize = authors
+                .iter()
+                .filter(|author| author.owner)
+                .map(|author| author.commits)
+                .sum();
+            let unresolved_teams: Vec<Owner> = owners
+                .iter()
+                .filter(|owner| {
+                    matches!(owner, Owner::Team(team) if roster.members_of(team).is_empty())
+                })
+                .cloned()
+                .collect();
+            let inactive_owners = owners
+                .iter()
+                .filter(|owner| {
+                    !unresolved_teams.contains(owner)
+                        && !authors
+                            .iter()
+                            .any(|author| roster.is_owner(owner, &author.author))
+                })
+                .cloned()
+                .collect();
+            let resolved = unresolved_teams.is_empty();
+            let dominant_non_owner = authors
+                .first()
+                .filter(|author| resolved && !author.owner && author.commits * 2 > commits)
+                .cloned();
+            RuleDrift {
+                line: rule.line,
+                pattern: rule.raw_pattern.clone(),
+                owners,
+                commits,
+                owners_commits,
+                inactive_owners,
+                owners_never_commit: resolved && commits > 0 && owners_commits == 0,
+                unresolved_teams,
+                dominant_non_owner,
+                authors,
+            }
+        })
+        .collect();
+    rules.sort_by_key(|rule| rule.line);
+    rules
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::{Commit, FileChange};
+    use crate::identity::parse_mailmap;
+
+    fn commit(email: &str, paths: Vec<&str>) -> CommitChanges {
+        CommitChanges {
+            commit: Commit {
+                sha: String::from("abc"),
+                author_name: String::from("Someone"),
+                author_email: email.into(),
+                timestamp: 0,
+                subject: String::new(),
+            },
+            files: paths
+                .into_iter()
+                .map(|path| FileChange {
+                    path: path.into(),
+                    added_lines: 2,
+                    deleted_lines: 1,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn flags_inactive_owners_and_dominant_non_owners() {
+        let codeowners = codeowners::from_reader(
+            "/api/ @org/api @dave\n/web/ @erin\n/docs/ @frank\n".as_bytes(),
+        );
+        let roster: Roster =
+            serde_json::from_str(r#"{ "teams": { "@org/api": ["@alice"] } }"#).unwrap();
+        let identities = IdentityResolver::new(
+            parse_mailmap("Bob <1+bob@users.noreply.github.com> <bob@laptop>\n"),
+            roster.clone(),
+        );
+        let history = vec![
+            commit(
+                "1+alice@users.noreply.github.com",
+                vec!["api/a.rs", "api/b.rs"],
+            ),
+            commit("bob@laptop", vec!["web/index.ts"]),
+            commit("bob@laptop", vec!["web/index.ts", "README.md"]),
+            commit("2+erin@users.noreply.github.com", vec!["web/app.ts"]),
+        ];
+        let rules = ownership_drift(&codeowners, &history, &identities, &roster);
+        assert_eq!(rules.len(), 3);
+
+        let api = &rules[0];
+        assert_eq!((api.commits, api.owners_commits), (1, 1));
+        assert_eq!(api.authors[0].lines, 6);
+        assert_eq!(api.inactive_owners, vec![Owner::Username("@dave".into())]);
+        assert!(!api.drifted());
+
+        let web = &rules[1];
+        assert_eq!((web.commits, web.owners_commits), (3, 1));
+        assert!(!web.owners_never_commit);
+        assert_eq!(
+            web.dominant_non_owner
+                .as_ref()
+                .map(|author| author.author.as_str()),
+            Some("@bob")
+        );
+
+        // nobody changed docs, so there is no evidence of drift
+        assert!(!rules[2].drifted());
+        assert_eq!(rules[2].inactive_owners.len(), 1);
+    }
+
+    #[test]
+    fn doesnt_flag_teams_without_known_members() {
+        let codeowners =
+            codeowners::from_reader("/api/ @org/api\n/web/ @org/web @erin\n".as_bytes());
+        let identities = IdentityResolver::new(vec![], Roster::default());
+        let history = vec![commit("alice@example.com", vec!["api/a.rs", "web/a.ts"])];
+        let rules = ownership_drift(&codeowners, &history, &identities, &Roster::default());
+
+        assert!(rules.iter().all(|rule| rule.unknown() && !rule.drifted()));
+        assert_eq!(
+            rules[0].unresolved_teams,
+            vec![Owner::Team("@org/api".into())]
+        );
+        assert!(rules[0].inactive_owners.is_empty());
+        // users are still checked
+        assert_eq!(
+            rules[1].inactive_owners,
+            vec![Owner::Username("@erin".into())]
+        );
+    }
+}
@@ -0,0 +1,473 @@
+//! Exports ownership of all files of a branch or of changed files as CSV, JSON, Markdown or HTML.
+//! Rows are written one by one, so big trees don't have to be collected in memory
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::str::FromStr;
+
+use crate::branch_diff::{
+    self, BranchCodeownersFiles, ChangeStatus, ChangedFile, CodeownersRevision,
+};
+use crate::git::{get_branch_files_vector, get_codeowners_content};
+use crate::{file_extension, join_owners, normalize_owners};
+use codeowners::Owners;
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /** Comma separated values with a header line */
+    Csv,
+    /** Array of objects with headers as keys */
+    Json,
+    /** Title and a table */
+    Markdown,
+    /** Self-contained page with styles and a filter input */
+    Html,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            "markdown" | "md" => Ok(ExportFormat::Markdown),
+            "html" => Ok(ExportFormat::Html),
+            _ => Err(format!(
+                "unknown export format {s}, expected csv, json, markdown or html"
+            )),
+        }
+    }
+}
+
+/** Same filters as the tree view has. None means that everything passes */
+#[derive(Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct ExportFilter {
+    /** File passes if any of its owners is listed */
+    pub owners: Option<Vec<String>>,
+    /** Extensions with leading dot, empty string for files without extension */
+    pub extensions: Option<Vec<String>>,
+}
+
+impl ExportFilter {
+    fn passes(&self, path: &str, owners: &[codeowners::Owner]) -> bool {
+        let owner_passes = match &self.owners {
+            Some(filter) => owners
+                .iter()
+                .any(|owner| filter.contains(&owner.to_string())),
+            None => true,
+        };
+        let extension_passes = match &self.extensions {
+            Some(filter) => filter
+                .iter()
+                .any(|extension| extension == file_extension(path)),
+            None => true,
+        };
+        owner_passes && extension_passes
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ExportResult {
+    pub path: String,
+    /** Count of exported files */
+    pub rows: usize,
+}
+
+fn status_name(status: ChangeStatus) -> &'static str {
+    match status {
+        ChangeStatus::Added => "added",
+        ChangeStatus::Modified => "modified",
+        ChangeStatus::Deleted => "deleted",
+        ChangeStatus::Renamed => "renamed",
+        ChangeStatus::Copied => "copied",
+    }
+}
+
+/** Owners joined with ", " and line of the rule which sets them. Line is empty if no rule matches */
+fn owners_columns(codeowners: &Owners, path: &str) -> (String, String) {
+    match codeowners.rule_of(path) {
+        Some(rule) => (
+            join_owners(&normalize_owners(Some(&rule.owners))),
+            rule.line.to_string(),
+        ),
+        None => (String::new(), String::new()),
+    }
+}
+
+/** Writes owners of every file of the branch. Returns count of written files */
+pub fn write_all_owners<W: Write + ?Sized>(
+    writer: &mut W,
+    abs_repo_path: &str,
+    branch: &str,
+    format: ExportFormat,
+    filter: &ExportFilter,
+) -> io::Result<usize> {
+    let codeowners_content = get_codeowners_content(abs_repo_path, branch);
+    let codeowners = codeowners::from_reader(codeowners_content.as_bytes());
+    let files = get_branch_files_vector(abs_repo_path, branch);
+    let rows = files.iter().filter_map(|path| {
+        let rule = codeowners.rule_of(path);
+        let owners = normalize_owners(rule.map(|rule| &rule.owners));
+        if !filter.passes(path, &owners) {
+            return None;
+        }
+        Some(vec![
+            path.clone(),
+            join_owners(&owners),
+            rule.map(|rule| rule.line.to_string()).unwrap_or_default(),
+        ])
+    });
+    write_table(
+        writer,
+        format,
+        &format!("Code owners of {branch}"),
+        &["Path", "Owners", "Rule line"],
+        rows,
+    )
+}
+
+/**
+ * Writes owners of files changed in the branch with requested CODEOWNERS revision.
+ * Deleted files and old paths of renamed files are always resolved with the base,
+ * like the diff view does. Returns count of written files
+ */
+pub fn write_branch_changes<W: Write + ?Sized>(
+    writer: &mut W,
+    abs_repo_path: &str,
+    branch: &str,
+    revision: CodeownersRevision,
+    format: ExportFormat,
+) -> io::Result<usize> {
+    let git_error = |error: String| io::Error::new(io::ErrorKind::Other, error);
+    let codeowners = BranchCodeownersFiles::load(abs_repo_path, branch).map_err(git_error)?;
+    let changes = branch_diff::get_branch_changes(abs_repo_path, branch).map_err(git_error)?;
+    let mut headers = vec![
+        "Path",
+        "Status",
+        "Old path",
+        "Old path owners",
+        "Added lines",
+        "Deleted lines",
+    ];
+    headers.extend(match revision {
+        CodeownersRevision::Both => vec![
+            "Base owners",
+            "Base rule line",
+            "Branch owners",
+            "Branch rule line",
+        ],
+        _ => vec!["Owners", "Rule line"],
+    });
+    let rows = changes
+        .iter()
+        .map(|change| branch_change_row(&codeowners, change, revision));
+    write_table(
+        writer,
+        format,
+        &format!("Code owners of files changed in {branch}"),
+        &headers,
+        rows,
+    )
+}
+
+/** Cells of a changed file in order of `write_branch_changes` headers */
+fn branch_change_row(
+    codeowners: &BranchCodeownersFiles,
+    change: &ChangedFile,
+    revision: CodeownersRevision,
+) -> Vec<String> {
+    let lines = |count: u32| {
+        if change.lines.binary {
+            String::from("binary")
+        } else {
+            count.to_string()
+        }
+    };
+    // moving a file out of a directory still needs review of the old owners
+    let old_owners = match (change.status, &change.old_path) {
+        (ChangeStatus::Renamed, Some(old_path)) => owners_columns(&codeowners.base, old_path).0,
+        _ => String::new(),
+    };
+    let mut row = vec![
+        change.path.clone(),
+        status_name(change.status).to_string(),
+        change.old_path.clone().unwrap_or_default(),
+        old_owners,
+        lines(change.lines.added_lines),
+        lines(change.lines.deleted_lines),
+    ];
+    let head = if change.status == ChangeStatus::Deleted {
+        &codeowners.base
+    } else {
+        &codeowners.head
+    };
+    let revisions = match revision {
+        CodeownersRevision::Base => vec![&codeowners.base],
+        CodeownersRevision::Head => vec![head],
+        CodeownersRevision::Both => vec![&codeowners.base, head],
+    };
+    for owners in revisions {
+        let (owners, line) = owners_columns(owners, &change.path);
+        row.push(owners);
+        row.push(line);
+    }
+    row
+}
+
+/** Streams rows written by `write` to the file at `path`. Error tells which file can't be written */
+pub fn export_to_file<F>(path: &str, write: F) -> Result<ExportResult, String>
+where
+    F: FnOnce(&mut BufWriter<File>) -> io::Result<usize>,
+{
+    let file = File::create(path).map_err(|error| format!("Can't create {path}: {error}"))?;
+    let mut writer = BufWriter::new(file);
+    let rows = write(&mut writer)
+        .and_then(|rows| writer.flush().map(|_| rows))
+        .map_err(|error| format!("Can't write {path}: {error}"))?;
+    Ok(ExportResult {
+        path: path.to_string(),
+        rows,
+    })
+}
+
+/** Writes rows as they come, returns count of rows */
+pub fn write_table<W, I>(
+    writer: &mut W,
+    format: ExportFormat,
+    title: &str,
+    headers: &[&str],
+    rows: I,
+) -> io::Result<usize>
+where
+    W: Write + ?Sized,
+    I: IntoIterator<Item = Vec<String>>,
+{
+    let headers: Vec<String> = headers.iter().map(|header| header.to_string()).collect();
+    match format {
+        ExportFormat::Csv => writeln!(writer, "{}", csv_line(&headers))?,
+        ExportFormat::Json => write!(writer, "[")?,
+        ExportFormat::Markdown => {
+            writeln!(writer, "# {}\n", markdown_cell(title))?;
+            writeln!(writer, "{}", markdown_line(&headers))?;
+            writeln!(writer, "|{}", "---|".repeat(headers.len()))?;
+        }
+        ExportFormat::Html => {
+            write!(
+                writer,
+                "{}",
+                HTML_HEAD.replace("{{title}}", &html_escape(title))
+            )?;
+            write!(writer, "<thead><tr>")?;
+            for header in &headers {
+                write!(writer, "<th>{}</th>", html_escape(header))?;
+            }
+            writeln!(writer, "</tr></thead>\n<tbody>")?;
+        }
+    }
+    let mut count = 0;
+    for row in rows {
+        match format {
+            ExportFormat::Csv => writeln!(writer, "{}", csv_line(&row))?,
+            ExportFormat::Json => {
+                let separator = if count == 0 { "" } else { "," };
+                write!(writer, "{separator}\n  {}", json_object(&headers, row))?;
+            }
+            ExportFormat::Markdown => writeln!(writer, "{}", markdown_line(&row))?,
+            ExportFormat::Html => {
+                write!(writer, "<tr>")?;
+                for cell in &row {
+                    write!(writer, "<td>{}</td>", html_escape(cell))?;
+                }
+                writeln!(writer, "</tr>")?;
+            }
+        }
+        count += 1;
+    }
+    match format {
+        ExportFormat::Csv => {}
+        ExportFormat::Json if count == 0 => writeln!(writer, "]")?,
+        ExportFormat::Json => writeln!(writer, "\n]")?,
+        ExportFormat::Markdown => writeln!(writer, "\n{count} files")?,
+        ExportFormat::Html => write!(
+            writer,
+            "{}",
+            HTML_TAIL.replace("{{count}}", &count.to_string())
+        )?,
+    }
+    Ok(count)
+}
+
+/** Quotes cells with commas, quotes or line breaks as RFC 4180 says */
+fn csv_line(cells: &[String]) -> String {
+    cells
+        .iter()
+        .map(|cell| {
+            if cell.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.clone()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/** Keeps keys in order of headers, which serde_json map would sort */
+fn json_object(headers: &[String], row: Vec<String>) -> String {
+    let fields: Vec<String> = headers
+        .iter()
+        .zip(row)
+        .map(|(header, cell)| {
+            format!(
+                "{}:{}",
+                serde_json::Value::from(header.as_str()),
+                serde_json::Value::from(cell)
+            )
+        })
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+fn markdown_cell(cell: &str) -> String {
+    cell.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace('\n', " ")
+}
+
+fn markdown_line(cells: &[String]) -> String {
+    let cells: Vec<String> = cells.iter().map(|cell| markdown_cell(cell)).collect();
+    format!("| {} |", cells.join(" | "))
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const HTML_HEAD: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{{title}}</title>
+<style>
+body { font-family: system-ui, sans-serif; margin: 2rem; color: #1f2328; }
+input { width: 24rem; padding: 0.4rem; margin-bottom: 1rem; }
+table { border-collapse: collapse; width: 100%; font-size: 14px; }
+th, td { border: 1px solid #d0d7de; padding: 0.3rem 0.6rem; text-align: left; }
+th { background: #f6f8fa; position: sticky; top: 0; }
+tr:nth-child(even) td { background: #fafbfc; }
+</style>
+</head>
+<body>
+<h1>{{title}}</h1>
+<input id="filter" type="search" placeholder="Filter rows...">
+<table>
+"#;
+
+const HTML_TAIL: &str = r#"</tbody>
+</table>
+<p>{{count}} files</p>
+<script>
+document.getElementById('filter').addEventListener('input', event => {
+  const query = event.target.value.toLowerCase();
+  for (const row of document.querySelectorAll('tbody tr')) {
+    row.hidden = !row.textContent.toLowerCase().includes(query);
+  }
+});
+</script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows() -> Vec<Vec<String>> {
+        vec![
+            vec!["src/a,b.rs".into(), "@org/core".into(), "2".into()],
+            vec!["docs/x|y.md".into(), "".into(), "".into()],
+        ]
+    }
+
+    fn export(format: ExportFormat) -> String {
+        let mut output: Vec<u8> = Vec::new();
+        let count = write_table(
+            &mut output,
+            format,
+            "Owners",
+            &["Path", "Owners", "Rule line"],
+            rows(),
+        )
+        .unwrap();
+        assert_eq!(count, 2);
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn escapes_cells_of_every_format() {
+        assert_eq!(
+            export(ExportFormat::Csv),
+            "Path,Owners,Rule line\n\"src/a,b.rs\",@org/core,2\ndocs/x|y.md,,\n"
+        );
+        assert_eq!(
+            export(ExportFormat::Markdown),
+            "# Owners\n\n| Path | Owners | Rule line |\n|---|---|---|\n| src/a,b.rs | @org/core | 2 |\n| docs/x\\|y.md |  |  |\n\n2 files\n"
+        );
+        assert_eq!(
+            export(ExportFormat::Json),
+            "[\n  {\"Path\":\"src/a,b.rs\",\"Owners\":\"@org/core\",\"Rule line\":\"2\"},\n  {\"Path\":\"docs/x|y.md\",\"Owners\":\"\",\"Rule line\":\"\"}\n]\n"
+        );
+        let html = export(ExportFormat::Html);
+        assert!(html.contains("<tr><td>src/a,b.rs</td><td>@org/core</td><td>2</td></tr>"));
+        assert!(html.contains("<p>2 files</p>"));
+    }
+
+    #[test]
+    fn filters_by_owners_and_extensions() {
+        let owners = vec![codeowners::Owner::Team("@org/core".into())];
+        let filter = ExportFilter {
+            owners: Some(vec!["@org/core".into()]),
+            extensions: Some(vec![".test.ts".into(), "".into()]),
+        };
+        assert!(filter.passes("src/a.test.ts", &owners));
+        assert!(filter.passes("Makefile", &owners));
+        assert!(!filter.passes("src/a.ts", &owners));
+        assert!(!filter.passes("src/a.test.ts", &[]));
+        assert!(ExportFilter::default().passes("src/a.ts", &[]));
+    }
+
+    #[test]
+    fn exports_owners_of_old_path_of_renamed_files() {
+        let codeowners = BranchCodeownersFiles::parse(
+            String::from("* @org/core\n/payments/ @org/payments\n"),
+            String::from("* @org/core\n/billing/ @org/billing\n"),
+        );
+        let changes = branch_diff::parse_name_status(
+            "R100\0payments/api.rs\0billing/api.rs\0M\0src/main.rs\0",
+        );
+        assert_eq!(
+            branch_change_row(&codeowners, &changes[0], CodeownersRevision::Both),
+            vec![
+                "billing/api.rs",
+                "renamed",
+                "payments/api.rs",
+                "@org/payments",
+                "0",
+                "0",
+                "@org/core",
+                "1",
+                "@org/billing",
+                "2"
+            ]
+        );
+        assert_eq!(
+            branch_change_row(&codeowners, &changes[1], CodeownersRevision::Head)[2..4],
+            [String::new(), String::new()]
+        );
+    }
+}
@@ -0,0 +1,401 @@
+//! Helpers which run git commands inside of the repository
+use serde::Serialize;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+
+/** Branch which is used as a base for all branch comparisons */
+pub const BASE_BRANCH: &str = "origin/main";
+
+/** Runs git with passed arguments inside of the repository and returns its stdout */
+pub fn run_git(abs_repo_path: &str, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .current_dir(abs_repo_path)
+        .arg("--no-pager")
+        .args(args)
+        .output()
+        .expect("git command failed");
+
+    if !output.status.success() {
+        eprintln!("Error: {}", String::from_utf8_lossy(&output.stderr));
+    }
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+/** Like `run_git`, but failing git is an error with its stderr instead of empty output */
+pub fn try_run_git(abs_repo_path: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .current_dir(abs_repo_path)
+        .arg("--no-pager")
+        .args(args)
+        .output()
+        .map_err(|error| format!("Can't run git in {abs_repo_path}: {error}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/** Return all files in the repo for passed branch */
+pub fn get_branch_files_vector(abs_repo_path: &str, branch: &str) -> Vec<String> {
+    run_git(abs_repo_path, &["ls-tree", "-r", branch, "--name-only"])
+        .split('\n')
+        // it is for latest line
+        .filter(|file_path| !file_path.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/** Returns content of codeowners file GitHub would use at passed branch. Empty if there is none */
+pub fn get_codeowners_content(abs_repo_path: &str, branch: &str) -> String {
+    get_github_codeowners(abs_repo_path, branch)
+        .map(|(_, content)| content)
+        .unwrap_or_default()
+}
+
+/** Locations of CODEOWNERS in the order GitHub looks for it, the first existing one is used */
+pub const CODEOWNERS_LOCATIONS: [&str; 3] = [".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/** Returns location and content of the CODEOWNERS file GitHub would use at passed revision */
+pub fn get_github_codeowners(
+    abs_repo_path: &str,
+    revision: &str,
+) -> Option<(&'static str, String)> {
+    CODEOWNERS_LOCATIONS.iter().find_map(|path| {
+        try_run_git(abs_repo_path, &["show", &format!("{revision}:{path}")])
+            .ok()
+            .map(|content| (*path, content))
+    })
+}
+
+/** Returns SHA of the commit passed revision points to, error if there is no such commit */
+pub fn resolve_commit(abs_repo_path: &str, revision: &str) -> Result<String, String> {
+    try_run_git(
+        abs_repo_path,
+        &["rev-parse", "--verify", &format!("{revision}^{{commit}}")],
+    )
+    .map(|output| output.trim().to_string())
+}
+
+/** Returns commit from which passed branch was forked from `base` */
+pub fn get_merge_base(abs_repo_path: &str, base: &str, branch: &str) -> Result<String, String> {
+    try_run_git(abs_repo_path, &["merge-base", base, branch])
+        .map(|output| output.trim().to_string())
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Commit {
+    pub sha: String,
+    pub author_name: String,
+    pub author_email: String,
+    /** Author date as unix timestamp in seconds */
+    pub timestamp: i64,
+    pub subject: String,
+}
+
+/**
+ * `git log` format which is parsed by `parse_log`. Fields are separated with unit separator,
+ * every commit starts with record separator, so commits can be split from their changed files
+ */
+const NUMSTAT_LOG_FORMAT: &str = "--format=%x1e%H%x1f%an%x1f%ae%x1f%at%x1f%s";
+
+/**
+ * Returns commits of passed revision range without merge commits, oldest first, with output of
+ * `git diff --raw --numstat -z -M -C` for every commit. All commits are read with one git call
+ */
+pub fn get_commit_diffs(abs_repo_path: &str, range: &str) -> Result<Vec<(Commit, String)>, String> {
+    let output = try_run_git(
+        abs_repo_path,
+        &[
+            "log",
+            "--no-merges",
+            "--reverse",
+            "-z",
+            "-M",
+            "-C",
+            "--raw",
+            "--numstat",
+            NUMSTAT_LOG_FORMAT,
+            range,
+        ],
+    )?;
+    Ok(parse_log_diffs(&output))
+}
+
+/** Splits `git log -z` output of `get_commit_diffs` into commits and their diff output */
+pub fn parse_log_diffs(output: &str) -> Vec<(Commit, String)> {
+    output
+        .split('\x1e')
+        .filter_map(|record| {
+            // with -z the commit line ends with NUL and a line break precedes the diff
+            let (header, diff) = record.split_once('\0').unwrap_or((record, ""));
+            let commit = parse_log(header).pop()?;
+            Some((commit, diff.trim_start_matches('\n').to_string()))
+        })
+        .collect()
+}
+
+pub fn parse_log(output: &str) -> Vec<Commit> {
+    output
+        .split('\n')
+        .filter_map(|line| {
+            let mut fields = line.split('\x1f');
+            Some(Commit {
+                sha: fields.next().filter(|sha| !sha.is_empty())?.to_string(),
+                author_name: fields.next()?.to_string(),
+                author_email: fields.next()?.to_string(),
+                timestamp: fields.next()?.parse().ok()?,
+                subject: fields.next().unwrap_or_default().to_string(),
+            })
+        })
+        .collect()
+}
+
+/** Lines of a file changed by a commit. Binary files have 0 lines */
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct FileChange {
+    pub path: String,
+    pub added_lines: u32,
+    pub deleted_lines: u32,
+}
+
+impl FileChange {
+    /** Added and deleted lines. A change of a binary file counts as one line, so it isn't lost in sums */
+    pub fn changed_lines(&self) -> u64 {
+        (u64::from(self.added_lines) + u64::from(self.deleted_lines)).max(1)
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct CommitChanges {
+    #[serde(flatten)]
+    pub commit: Commit,
+    pub files: Vec<FileChange>,
+}
+
+/**
+ * Returns commits of the revision newer than `since` with files they changed, newest first.
+ * `since` is anything `git log --since` accepts, e.g. `6 months ago`. Renames are reported as
+ * deletion of the old path and addition of the new one, merge commits are skipped.
+ */
+pub fn get_commit_changes(abs_repo_path: &str, revision: &str, since: &str) -> Vec<CommitChanges> {
+    let output = run_git(
+        abs_repo_path,
+        &[
+            "log",
+            "--no-merges",
+            "--no-renames",
+            "--numstat",
+            &format!("--since={since}"),
+            NUMSTAT_LOG_FORMAT,
+            revision,
+        ],
+    );
+    parse_log_numstat(&output)
+}
+
+/** Parses `git log --numstat` output where every commit starts with `NUMSTAT_LOG_FORMAT` */
+pub fn parse_log_numstat(output: &str) -> Vec<CommitChanges> {
+    output
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut lines = record.lines();
+            let commit = parse_log(lines.next()?).pop()?;
+            let files = lines
+                .filter_map(|line| {
+                    let mut fields = line.splitn(3, '\t');
+                    let added_lines = fields.next()?.parse().unwrap_or(0);
+                    let deleted_lines = fields.next()?.parse().unwrap_or(0);
+                    Some(FileChange {
+                        path: fields.next()?.to_string(),
+                        added_lines,
+                        deleted_lines,
+                    })
+                })
+                .collect();
+            Some(CommitChanges { commit, files })
+        })
+        .collect()
+}
+
+/** Values of `key` trailers of all commits of passed revision range, e.g. `Approved-by` */
+pub fn get_trailers(abs_repo_path: &str, range: &str, key: &str) -> Vec<String> {
+    run_git(
+        abs_repo_path,
+        &[
+            "log",
+            &format!("--format=%(trailers:key={key},valueonly)"),
+            range,
+        ],
+    )
+    .lines()
+    .map(str::trim)
+    .filter(|value| !value.is_empty())
+    .map(String::from)
+    .collect()
+}
+
+/** Returns SHA of the tree of passed revision. Empty if revision doesn't exist */
+pub fn get_tree_sha(abs_repo_path: &str, revision: &str) -> String {
+    run_git(
+        abs_repo_path,
+        &["rev-parse", &format!("{revision}^{{tree}}")],
+    )
+    .trim()
+    .to_string()
+}
+
+/** Returns absolute path inside of git directory, e.g. for caches. Works for worktrees too */
+pub fn get_git_path(abs_repo_path: &str, path: &str) -> std::path::PathBuf {
+    let git_path = run_git(abs_repo_path, &["rev-parse", "--git-path", path]);
+    std::path::Path::new(abs_repo_path).join(git_path.trim())
+}
+
+/** File of a tree with SHA and size of its blob */
+#[derive(Clone, Debug, PartialEq)]
+pub struct TreeBlob {
+    pub path: String,
+    pub sha: String,
+    pub size: u64,
+}
+
+/** Returns blobs of the tree of passed revision. Submodules are skipped */
+pub fn get_tree_blobs(abs_repo_path: &str, revision: &str) -> Vec<TreeBlob> {
+    parse_ls_tree(&run_git(
+        abs_repo_path,
+        &["ls-tree", "-r", "-l", "-z", revision],
+    ))
+}
+
+/** Parses `git ls-tree -r -l -z` output: `<mode> <type> <sha> <size>\t<path>` separated with NUL */
+pub fn parse_ls_tree(output: &str) -> Vec<TreeBlob> {
+    output
+        .split('\0')
+        .filter_map(|entry| {
+            let (info, path) = entry.split_once('\t')?;
+            let mut fields = info.split_whitespace();
+            let object_type = fields.nth(1)?;
+            let sha = fields.next()?;
+            if object_type != "blob" {
+                return None;
+            }
+            Some(TreeBlob {
+                path: path.to_string(),
+                sha: sha.to_string(),
+                size: fields.next()?.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+/**
+ * Reads content of blobs with one `git cat-file --batch` process and passes it to `on_blob`
+ * together with the SHA. Content is streamed, so only one blob is kept in memory at a time.
+ */
+pub fn for_each_blob<F>(abs_repo_path: &str, shas: Vec<String>, mut on_blob: F)
+where
+    F: FnMut(&str, &[u8]),
+{
+    let mut child = Command::new("git")
+        .current_dir(abs_repo_path)
+        .args(["cat-file", "--batch"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("git command failed");
+    let mut stdin = child.stdin.take().unwrap();
+    // writing from another thread, otherwise both processes wait for each other on full pipes
+    let writer = thread::spawn(move || {
+        for sha in shas {
+            if writeln!(stdin, "{sha}").is_err() {
+                break;
+            }
+        }
+    });
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut header = String::new();
+    let mut content: Vec<u8> = Vec::new();
+    loop {
+        header.clear();
+        if stdout.read_line(&mut header).unwrap_or(0) == 0 {
+            break;
+        }
+        // `<sha> <type> <size>` or `<sha> missing`
+        let fields: Vec<&str> = header.split_whitespace().collect();
+        let size: usize = match fields.get(2).and_then(|size| size.parse().ok()) {
+            Some(size) => size,
+            None => continue,
+        };
+        content.resize(size + 1, 0);
+        if stdout.read_exact(&mut content).is_err() {
+            break;
+        }
+        on_blob(fields[0], &content[..size]);
+    }
+    writer.join().ok();
+    child.wait().ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_log() {
+        let output = "abc\x1fJane Doe\x1fjane@example.com\x1f1700000000\x1fFix: a\x1fb\n";
+        assert_eq!(
+            parse_log(output),
+            vec![Commit {
+                sha: "abc".into(),
+                author_name: "Jane Doe".into(),
+                author_email: "jane@example.com".into(),
+                timestamp: 1700000000,
+                subject: "Fix: a".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn parses_log_with_numstat() {
+        let output = "\x1eabc\x1fJane\x1fjane@example.com\x1f1700000000\x1fFix\n\n3\t1\tsrc/a.rs\n-\t-\tlogo.png\n\x1edef\x1fBob\x1fbob@example.com\x1f1600000000\x1fInit\n";
+        let commits = parse_log_numstat(output);
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].commit.sha, "abc");
+        assert_eq!(
+            commits[0].files,
+            vec![
+                FileChange {
+                    path: "src/a.rs".into(),
+                    added_lines: 3,
+                    deleted_lines: 1,
+                },
+                FileChange {
+                    path: "logo.png".into(),
+                    added_lines: 0,
+                    deleted_lines: 0,
+                },
+            ]
+        );
+        assert!(commits[1].files.is_empty());
+        assert_eq!(commits[0].files[0].changed_lines(), 4);
+        assert_eq!(commits[0].files[1].changed_lines(), 1);
+    }
+
+    #[test]
+    fn parses_ls_tree() {
+        let output =
+            "100644 blob 1a2b     120\tsrc/main.rs\x00160000 commit 3c4d       -\tvendor/lib\0";
+        assert_eq!(
+            parse_ls_tree(output),
+            vec![TreeBlob {
+                path: "src/main.rs".into(),
+                sha: "1a2b".into(),
+                size: 120,
+            }]
+        );
+    }
+}
@@ -0,0 +1,207 @@
+//! Maps commit authors to CODEOWNERS identities with `.mailmap` of the repository and roster aliases
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+use crate::roster::Roster;
+
+/** Line of `.mailmap`. Commit name is matched only if it is set */
+#[derive(Clone, Debug, PartialEq)]
+pub struct MailmapEntry {
+    pub proper_name: Option<String>,
+    pub proper_email: Option<String>,
+    pub commit_name: Option<String>,
+    pub commit_email: String,
+}
+
+/** Author of a commit with canonical name and email and username if it is known */
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Identity {
+    pub name: String,
+    pub email: String,
+    /** Username with leading @. Null if neither roster nor email tells it */
+    pub username: Option<String>,
+}
+
+pub struct IdentityResolver {
+    mailmap: Vec<MailmapEntry>,
+    roster: Roster,
+}
+
+impl IdentityResolver {
+    pub fn new(mailmap: Vec<MailmapEntry>, roster: Roster) -> IdentityResolver {
+        IdentityResolver { mailmap, roster }
+    }
+
+    /** Reads `.mailmap` of the working tree. Missing file means there is nothing to map */
+    pub fn load(abs_repo_path: &str, roster: Roster) -> IdentityResolver {
+        let content =
+            fs::read_to_string(Path::new(abs_repo_path).join(".mailmap")).unwrap_or_default();
+        IdentityResolver::new(parse_mailmap(&content), roster)
+    }
+
+    /**
+     * Canonical name and email like `git log --use-mailmap` shows.
+     * Entry with matching commit name wins over entry with email only.
+     */
+    pub fn canonical(&self, name: &str, email: &str) -> (String, String) {
+        let matching = |with_name: bool| {
+            self.mailmap.iter().find(|entry| {
+                entry.commit_email.eq_ignore_ascii_case(email)
+                    && match &entry.commit_name {
+                        Some(commit_name) => with_name && commit_name.eq_ignore_ascii_case(name),
+                        None => !with_name,
+                    }
+            })
+        };
+        match matching(true).or_else(|| matching(false)) {
+            Some(entry) => (
+                entry.proper_name.as_deref().unwrap_or(name).to_string(),
+                entry.proper_email.as_deref().unwrap_or(email).to_string(),
+            ),
+            None => (name.to_string(), email.to_string()),
+        }
+    }
+
+    /**
+     * Resolves author to canonical identity. Username is looked up in roster aliases
+     * by canonical and commit emails and names, then taken from GitHub noreply email.
+     */
+    pub fn resolve(&self, name: &str, email: &str) -> Identity {
+        let (canonical_name, canonical_email) = self.canonical(name, email);
+        let username = [
+            canonical_email.as_str(),
+            email,
+            canonical_name.as_str(),
+            name,
+        ]
+        .iter()
+        .find_map(|alias| self.roster.username_by_alias(alias))
+        .map(String::from)
+        .or_else(|| github_username(&canonical_email))
+        .or_else(|| github_username(email));
+        Identity {
+            name: canonical_name,
+            email: canonical_email,
+            username,
+        }
+    }
+}
+
+/** Username of `123+name@users.noreply.github.com` or `name@users.noreply.github.com` */
+pub fn github_username(email: &str) -> Option<String> {
+    let (local, domain) = email.rsplit_once('@')?;
+    if !domain.eq_ignore_ascii_case("users.noreply.github.com") {
+        return None;
+    }
+    let login = local.split_once('+').map_or(local, |(_, login)| login);
+    if login.is_empty() {
+        None
+    } else {
+        Some(format!("@{login}"))
+    }
+}
+
+/**
+ * Parses `.mailmap` lines in any of forms
+ * `Proper Name <commit@email>`, `<proper@email> <commit@email>`,
+ * `Proper Name <proper@email> <commit@email>`, `Proper Name <proper@email> Commit Name <commit@email>`.
+ */
+pub fn parse_mailmap(content: &str) -> Vec<MailmapEntry> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut parts: Vec<(Option<String>, String)> = Vec::new();
+            let mut rest = line;
+            while let Some(start) = rest.find('<') {
+                let end = start + rest[start..].find('>')?;
+                let name = rest[..start].trim();
+                let name = if name.is_empty() {
+                    None
+                } else {
+                    Some(name.to_string())
+                };
+                parts.push((name, rest[start + 1..end].trim().to_string()));
+                rest = &rest[end + 1..];
+            }
+            let mut parts = parts.into_iter();
+            let (proper_name, first_email) = parts.next()?;
+            Some(match parts.next() {
+                None => MailmapEntry {
+                    proper_name,
+                    proper_email: None,
+                    commit_name: None,
+                    commit_email: first_email,
+                },
+                Some((commit_name, commit_email)) => MailmapEntry {
+                    proper_name,
+                    proper_email: Some(first_email),
+                    commit_name,
+                    commit_email,
+                },
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAILMAP: &str = r"# comment
+Alice Smith <alice@example.com>
+<alice@example.com> <alice@old-laptop.local>
+Bob <bob@example.com> Bobby <BOB@corp.example.com>
+";
+
+    fn resolver() -> IdentityResolver {
+        let roster: Roster = serde_json::from_str(
+            r#"{ "aliases": { "@alice": ["alice@example.com"], "@bob": ["Bob"] } }"#,
+        )
+        .unwrap();
+        IdentityResolver::new(parse_mailmap(MAILMAP), roster)
+    }
+
+    #[test]
+    fn parses_mailmap_forms() {
+        let entries = parse_mailmap(MAILMAP);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            entries[2],
+            MailmapEntry {
+                proper_name: Some("Bob".into()),
+                proper_email: Some("bob@example.com".into()),
+                commit_name: Some("Bobby".into()),
+                commit_email: "BOB@corp.example.com".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn resolves_authors_to_usernames() {
+        let resolver = resolver();
+        assert_eq!(
+            resolver.resolve("alice", "alice@old-laptop.local"),
+            Identity {
+                name: "alice".into(),
+                email: "alice@example.com".into(),
+                username: Some("@alice".into()),
+            }
+        );
+        let bob = resolver.resolve("Bobby", "bob@corp.example.com");
+        assert_eq!(bob.email, "bob@example.com");
+        assert_eq!(bob.username, Some("@bob".into()));
+        // name must match because the entry has commit name
+        assert_eq!(
+            resolver.resolve("Robert", "bob@corp.example.com").username,
+            None
+        );
+        assert_eq!(
+            resolver
+                .resolve("Carol", "123+carol@users.noreply.github.com")
+                .username,
+            Some("@carol".into())
+        );
+    }
+}
@@ -0,0 +1,297 @@
+//! How knowledge of code is spread among contributors per owner and per directory, e.g. bus factor
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::all_owners::{self, DirectoryOwners};
+use crate::git::{self, CommitChanges};
+use crate::identity::IdentityResolver;
+use crate::roster::Roster;
+use crate::rounded_percent;
+use codeowners::Owner;
+
+/** History window used by default */
+pub const DEFAULT_MONTHS: u32 = 12;
+
+#[derive(Serialize, Clone, Debug, PartialEq, Default)]
+pub struct Concentration {
+    /** Distinct authors who changed files */
+    pub contributors: usize,
+    pub commits: usize,
+    /** Added and deleted lines, a change of a binary file counts as one line */
+    pub lines: u64,
+    /** Author who changed the most lines. Null without changes */
+    pub top_contributor: Option<String>,
+    /** Share of lines changed by the top contributor, percent rounded to one decimal */
+    pub top_share: f64,
+    /** The fewest contributors who together changed more than half of the lines. 0 without changes */
+    pub bus_factor: usize,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct OwnerKnowledge {
+    /** Null for unowned files */
+    pub owner: Option<Owner>,
+    #[serde(flatten)]
+    pub concentration: Concentration,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct DirectoryKnowledge {
+    /** Path without trailing slash */
+    pub path: String,
+    /** 1 for top-level directories */
+    pub depth: usize,
+    #[serde(flatten)]
+    pub concentration: Concentration,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct KnowledgeReport {
+    pub branch: String,
+    pub months: u32,
+    pub total: Concentration,
+    /** Every owner of the branch, the most changed lines first */
+    pub owners: Vec<OwnerKnowledge>,
+    /** Directories down to the requested depth, parents before children */
+    pub directories: Vec<DirectoryKnowledge>,
+}
+
+/** Changes of an area: commits are counted once even if they change several files of it */
+#[derive(Default)]
+struct Contributions {
+    commits: usize,
+    last_commit: Option<usize>,
+    lines_by_author: BTreeMap<String, u64>,
+}
+
+impl Contributions {
+    fn add(&mut self, commit: usize, author: &str, lines: u64) {
+        if self.last_commit != Some(commit) {
+            self.last_commit = Some(commit);
+            self.commits += 1;
+        }
+        *self.lines_by_author.entry(author.to_string()).or_default() += lines;
+    }
+
+    fn concentration(&self) -> Concentration {
+        let mut authors: Vec<(&String, u64)> = self
+            .lines_by_author
+            .iter()
+            .map(|(author, lines)| (author, *lines))
+            .collect();
+        authors.sort_by_key(|(_, lines)| Reverse(*lines));
+        let lines: u64 = authors.iter().map(|(_, lines)| lines).sum();
+        let mut covered = 0;
+        let bus_factor = authors
+            .iter()
+            .take_while(|(_, author_lines)| {
+                let needed = covered * 2 <= lines;
+                covered += author_lines;
+                needed
+            })
+            .count();
+        Concentration {
+            contributors: authors.len(),
+            commits: self.commits,
+            lines,
+            top_contributor: authors.first().map(|(author, _)| author.to_string()),
+            top_share: authors
+                .first()
+                .and_then(|(_, top_lines)| rounded_percent(*top_lines, lines))
+                .unwrap_or_default(),
+            bus_factor,
+        }
+    }
+}
+
+/** Concentration of changes of the last `months` in files of the branch per owner and per directory */
+pub fn get_knowledge_report(
+    abs_repo_path: &str,
+    branch: &str,
+    months: u32,
+    depth: usize,
+    roster: &Roster,
+) -> KnowledgeReport {
+    let root = all_owners::get_all_codeowners_for_branch_struct(abs_repo_path, branch, |_, _| {});
+    let history = git::get_commit_changes(abs_repo_path, branch, &format!("{months} months ago"));
+    let identities = IdentityResolver::load(abs_repo_path, roster.clone());
+    let (total, owners, directories) = knowledge(&root, &history, &identities, depth);
+    KnowledgeReport {
+        branch: branch.to_string(),
+        months,
+        total,
+        owners,
+        directories,
+    }
+}
+
+/** Files which don't exist in the branch anymore are skipped, nobody has to know them */
+pub fn knowledge(
+    root: &DirectoryOwners,
+    history: &[CommitChanges],
+    identities: &IdentityResolver,
+    depth: usize,
+) -> (Concentration, Vec<OwnerKnowledge>, Vec<DirectoryKnowledge>) {
+    let owners_by_path = root.owners_by_path();
+    let directory_paths = root.directory_paths(depth);
+    let mut total = Contributions::default();
+    let mut owners: BTreeMap<Option<&Owner>, Contributions> = BTreeMap::new();
+    if owners_by_path
+        .values()
+        .any(|file_owners| file_owners.is_empty())
+    {
+        owners.insert(None, Contributions::default());
+    }
+    for owner in owners_by_path
+        .values()
+        .flat_map(|file_owners| file_owners.iter())
+    {
+        owners.entry(Some(owner)).or_default();
+    }
+    let mut directories: HashMap<&str, Contributions> = directory_paths
+        .iter()
+        .map(|path| (path.as_str(), Contributions::default()))
+        .collect();
+
+    for (index, changes) in history.iter().enumerate() {
+        let identity =
+            identities.resolve(&changes.commit.author_name, &changes.commit.author_email);
+        let author = identity.username.unwrap_or(identity.email);
+        for file in &changes.files {
+            let file_owners = match owners_by_path.get(&file.path) {
+                Some(file_owners) => file_owners,
+                None => continue,
+            };
+            let lines = file.changed_lines();
+            total.add(index, &author, lines);
+            if file_owners.is_empty() {
+                owners.entry(None).or_default().add(index, &author, lines);
+            }
+            for owner in file_owners.iter() {
+                owners
+                    .entry(Some(owner))
+                    .or_default()
+                    .add(index, &author, lines);
+            }
+            for directory in all_owners::parent_directories(&file.path, depth) {
+                if let Some(contributions) = directories.get_mut(directory) {
+                    contributions.add(index, &author, lines);
+                }
+            }
+        }
+    }
+
+    let mut owners: Vec<OwnerKnowledge> = owners
+        .into_iter()
+        .map(|(owner, contributions)| OwnerKnowledge {
+            owner: owner.cloned(),
+            concentration: contributions.concentration(),
+        })
+        .collect();
+    owners.sort_by_key(|knowledge| Reverse(knowledge.concentration.lines));
+    let directories = directory_paths
+        .iter()
+        .map(|path| DirectoryKnowledge {
+            path: path.clone(),
+            depth: path.matches('/').count() + 1,
+            concentration: directories[path.as_str()].concentration(),
+        })
+        .collect();
+    (total.concentration(), owners, directories)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::all_owners::FileOwners;
+    use crate::git::{Commit, FileChange};
+
+    fn commit(email: &str, files: Vec<(&str, u32)>) -> CommitChanges {
+        CommitChanges {
+            commit: Commit {
+                sha: String::from("abc"),
+                author_name: String::from("Someone"),
+                author_email: email.into(),
+                timestamp: 0,
+                subject: String::new(),
+            },
+            files: files
+                .into_iter()
+                .map(|(path, lines)| FileChange {
+                    path: path.into(),
+                    added_lines: lines,
+                    deleted_lines: 0,
+                })
+                .collect(),
+        }
+    }
+
+    fn file(name: &str, owners: Vec<Owner>) -> FileOwners {
+        FileOwners {
+            name: name.into(),
+            owner: String::new(),
+            owners,
+        }
+    }
+
+    #[test]
+    fn estimates_bus_factor_per_owner_and_directory() {
+        let core = Owner::Team("@org/core".into());
+        let root = DirectoryOwners {
+            name: String::new(),
+            files: vec![file("README.md", vec![])],
+            directories: vec![DirectoryOwners {
+                name: "src".into(),
+                files: vec![file("main.rs", vec![core.clone()])],
+                directories: vec![DirectoryOwners {
+                    name: "gen".into(),
+                    files: vec![file("a.rs", vec![core.clone()])],
+                    directories: vec![],
+                    owner: None,
+                }],
+                owner: None,
+            }],
+            owner: None,
+        };
+        let history = vec![
+            commit(
+                "alice@example.com",
+                vec![("src/main.rs", 70), ("src/gen/a.rs", 10)],
+            ),
+            commit(
+                "bob@example.com",
+                vec![("src/main.rs", 10), ("README.md", 5)],
+            ),
+            commit(
+                "carol@example.com",
+                vec![("src/gen/a.rs", 10), ("old.rs", 100)],
+            ),
+            commit("alice@example.com", vec![("logo.png", 0), ("README.md", 5)]),
+        ];
+        let identities = IdentityResolver::new(vec![], Roster::default());
+        let (total, owners, directories) = knowledge(&root, &history, &identities, 1);
+
+        assert_eq!(total.commits, 4);
+        assert_eq!(total.contributors, 3);
+        assert_eq!(total.lines, 110);
+        // 85 of 110 lines is 77.27%
+        assert_eq!(total.top_share, 77.3);
+
+        assert_eq!(owners[0].owner, Some(core));
+        let core_knowledge = &owners[0].concentration;
+        assert_eq!(core_knowledge.commits, 3);
+        assert_eq!(
+            core_knowledge.top_contributor.as_deref(),
+            Some("alice@example.com")
+        );
+        assert_eq!(core_knowledge.top_share, 80.0);
+        assert_eq!(core_knowledge.bus_factor, 1);
+        assert_eq!(owners[1].owner, None);
+        assert_eq!(owners[1].concentration.bus_factor, 2);
+
+        assert_eq!(directories.len(), 1);
+        assert_eq!(directories[0].path, "src");
+        assert_eq!(directories[0].concentration.contributors, 3);
+    }
+}
@@ -0,0 +1,62 @@
+//! Backend of the codeowners viewer shared by the desktop app and the command line tool
+use codeowners::Owner;
+
+pub mod all_owners;
+pub mod approvals;
+pub mod branch_check;
+pub mod branch_commits;
+pub mod branch_diff;
+pub mod churn;
+pub mod codeowners_diff;
+pub mod coverage;
+pub mod coverage_trend;
+pub mod drift;
+pub mod export;
+pub mod git;
+pub mod identity;
+pub mod knowledge;
+pub mod owner_suggestions;
+pub mod ownership_history;
+pub mod personal;
+pub mod pr_summary;
+pub mod reviewers;
+pub mod roster;
+pub mod statistics;
+pub mod template;
+
+/**
+ * Owners sorted by kind and name without duplicates.
+ * The same set of owners looks the same regardless of order in CODEOWNERS.
+ */
+pub fn normalize_owners(owners_vec: Option<&Vec<Owner>>) -> Vec<Owner> {
+    let mut owners = owners_vec.cloned().unwrap_or_default();
+    owners.sort();
+    owners.dedup();
+    owners
+}
+
+/** Owners joined with ", " for display. Empty string for unowned files */
+pub fn join_owners(owners: &[Owner]) -> String {
+    owners
+        .iter()
+        .map(|owner| format!("{owner}"))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+pub fn get_joined_codeowners(owners_vec: Option<&Vec<Owner>>) -> Option<String> {
+    owners_vec.map(|_| join_owners(&normalize_owners(owners_vec)))
+}
+
+/** Extension starts from the first dot of the file name like in the tree view filter. Empty if there is no dot */
+pub fn file_extension(path: &str) -> &str {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    name.find('.').map_or("", |index| &name[index..])
+}
+
+/** Share of `part` in `total` as percent rounded half up to one decimal. None if total is 0 */
+pub fn rounded_percent(part: u64, total: u64) -> Option<f64> {
+    (part * 2000 + total)
+        .checked_div(total * 2)
+        .map(|permille| permille as f64 / 10.0)
+}
@@ -1,8 +1,12 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
-use std::{collections::HashMap, process::Command};
-pub mod codeowners_file_parser;
-use serde::ser::{SerializeStruct, Serializer};
+use app_lib::git::{get_branch_files_vector, get_codeowners_content};
+use app_lib::roster::{self, Roster};
+use app_lib::{
+    all_owners, approvals, branch_commits, branch_diff, churn, codeowners_diff, coverage,
+    coverage_trend, drift, export, get_joined_codeowners, knowledge, owner_suggestions,
+    ownership_history, personal, pr_summary, reviewers, statistics,
+};
 use serde::Serialize;
 use tauri::Manager;
 
@@ -15,6 +19,24 @@ fn main() {
             get_all_codeowners_for_branch,
             get_changed_codeowners_for_branch,
             get_codeowners_for_branch_file,
+            get_commits_codeowners_for_branch,
+            get_codeowners_diff_between_revisions,
+            validate_codeowners_with_roster,
+            get_review_files_for_branch,
+            get_owned_files_for_branch,
+            get_minimal_reviewers_for_branch,
+            simulate_approvals_for_branch,
+            get_pull_request_summary_for_branch,
+            export_all_codeowners_for_branch,
+            export_changed_codeowners_for_branch,
+            get_repo_statistics_for_branch,
+            get_coverage_report_for_branch,
+            get_coverage_trend,
+            get_owner_suggestions_for_branch,
+            get_ownership_drift_for_branch,
+            get_knowledge_report_for_branch,
+            get_churn_report_for_branch,
+            get_file_ownership_history,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -35,8 +57,21 @@ fn get_all_codeowners_for_branch(
     branch: &str,
     session_id: &str,
 ) -> String {
-    let all_owners =
-        get_all_codeowners_for_branch_struct(app_handle, abs_repo_path, branch, session_id);
+    let all_owners = all_owners::get_all_codeowners_for_branch_struct(
+        abs_repo_path,
+        branch,
+        |files_handled, files_total| {
+            let payload = AllCodeownersProgressPayload {
+                files_handled: files_handled as u32,
+                files_total: files_total as u32,
+                session_id: session_id.to_string(),
+            };
+            app_handle
+                .emit_all("all-codeowners-progress", payload)
+                .unwrap();
+            println!("handled {files_handled} from {files_total}");
+        },
+    );
     serde_json::to_string(&all_owners).unwrap()
 }
 
@@ -44,251 +79,333 @@ fn get_all_codeowners_for_branch(
 #[tauri::command(async)]
 fn get_codeowners_for_branch_file(abs_repo_path: &str, branch: &str, file: &str) -> String {
     let codeowners_content = get_codeowners_content(abs_repo_path, branch);
-    let codeowners = codeowners_file_parser::from_reader(codeowners_content.as_bytes());
+    let codeowners = codeowners::from_reader(codeowners_content.as_bytes());
     get_joined_codeowners(codeowners.of(file)).unwrap_or(String::from(""))
 }
 
 /** Key is team or empty, value is changed files for branch */
 #[tauri::command(async)]
-fn get_changed_codeowners_for_branch(abs_repo_path: &str, branch: &str) -> String {
-    let codeowners_content = get_codeowners_content(abs_repo_path, branch);
-    let codeowners = codeowners_file_parser::from_reader(codeowners_content.as_bytes());
-    let branch_diff = get_branch_diff(abs_repo_path, branch);
+fn get_changed_codeowners_for_branch(
+    abs_repo_path: &str,
+    branch: &str,
+    codeowners_revision: Option<branch_diff::CodeownersRevision>,
+    grouping: Option<branch_diff::OwnersGrouping>,
+) -> Result<String, String> {
+    let result = branch_diff::get_changed_codeowners(
+        abs_repo_path,
+        branch,
+        codeowners_revision.unwrap_or_default(),
+        grouping.unwrap_or_default(),
+    )?;
+    Ok(serde_json::to_string(&result).unwrap())
+}
 
-    let mut owners_dictionary: HashMap<String, Vec<String>> = HashMap::new();
-    for file_path in branch_diff.split("\n") {
-        if file_path.is_empty() {
-            // it is for latest line
-            continue;
-        }
-        let owner_team = get_joined_codeowners(codeowners.of(file_path));
+/** Commits of the branch which are not in main with codeowners groups touched by every commit */
+#[tauri::command(async)]
+fn get_commits_codeowners_for_branch(
+    abs_repo_path: &str,
+    branch: &str,
+    codeowners_revision: Option<branch_diff::CodeownersRevision>,
+    grouping: Option<branch_diff::OwnersGrouping>,
+) -> Result<String, String> {
+    let result = branch_commits::get_commits_codeowners(
+        abs_repo_path,
+        branch,
+        codeowners_revision.unwrap_or_default(),
+        grouping.unwrap_or_default(),
+    )?;
+    Ok(serde_json::to_string(&result).unwrap())
+}
 
-        owners_dictionary
-            .entry(owner_team.unwrap_or(String::new()))
-            .and_modify(|e| e.push(file_path.to_string()))
-            .or_insert(vec![file_path.to_string()]);
-    }
-    let mut result: Vec<FrontendCodeowner> = owners_dictionary
-        .into_iter()
-        .map(|(owners, files)| FrontendCodeowner { owners, files })
-        .collect::<Vec<FrontendCodeowner>>();
-    // We have to send stable data
-    result.sort_by(|a, b| a.owners.cmp(&b.owners));
-    serde_json::to_string(&result).unwrap()
+/** Files whose owners differ between CODEOWNERS of two revisions, grouped by old -> new owners */
+#[tauri::command(async)]
+fn get_codeowners_diff_between_revisions(
+    abs_repo_path: &str,
+    from_revision: &str,
+    to_revision: &str,
+) -> Result<String, String> {
+    let result = codeowners_diff::get_codeowners_diff(abs_repo_path, from_revision, to_revision)?;
+    Ok(serde_json::to_string(&result).unwrap())
 }
 
-/** Returns difference as list of changed files between passed branch and main */
-fn get_branch_diff(abs_repo_path: &str, branch: &str) -> String {
-    let output = Command::new("git")
-        .current_dir(abs_repo_path)
-        .arg("--no-pager")
-        .arg("diff")
-        .arg("--name-only")
-        .arg(format!("origin/main...{branch}"))
-        .output()
-        .expect("git command failed");
+/** Parser diagnostics and owners of branch CODEOWNERS which are missing in the roster file */
+#[tauri::command(async)]
+fn validate_codeowners_with_roster(
+    abs_repo_path: &str,
+    branch: &str,
+    roster_path: &str,
+) -> Result<String, String> {
+    let roster = Roster::load(abs_repo_path, roster_path)?;
+    let result = roster::validate_branch_owners(abs_repo_path, branch, &roster);
+    Ok(serde_json::to_string(&result).unwrap())
+}
 
-    if !output.status.success() {
-        println!("Error: {}", String::from_utf8_lossy(&output.stderr));
-    }
-    let content: String = String::from_utf8_lossy(&output.stdout).to_string();
-    content
+/** Changed files of the branch which need review of `me` directly or through teams of the roster */
+#[tauri::command(async)]
+fn get_review_files_for_branch(
+    abs_repo_path: &str,
+    branch: &str,
+    codeowners_revision: Option<branch_diff::CodeownersRevision>,
+    roster_path: &str,
+    me: &str,
+) -> Result<String, String> {
+    let roster = Roster::load(abs_repo_path, roster_path)?;
+    let result = personal::get_review_files(
+        abs_repo_path,
+        branch,
+        codeowners_revision.unwrap_or_default(),
+        &roster,
+        me,
+    )?;
+    Ok(serde_json::to_string(&result).unwrap())
 }
 
-/** Return all files in the repo for passed branch */
-fn get_branch_files_vector(abs_repo_path: &str, branch: &str) -> Vec<String> {
-    let output = Command::new("git")
-        .current_dir(abs_repo_path)
-        .arg("ls-tree")
-        .arg("-r")
-        .arg(branch)
-        .arg("--name-only")
-        .output()
-        .expect("git command failed");
+/** All files of the branch owned by the person directly or through teams of the roster */
+#[tauri::command(async)]
+fn get_owned_files_for_branch(
+    abs_repo_path: &str,
+    branch: &str,
+    roster_path: &str,
+    person: &str,
+) -> Result<String, String> {
+    let roster = Roster::load(abs_repo_path, roster_path)?;
+    let result = personal::get_owned_files(abs_repo_path, branch, &roster, person);
+    Ok(serde_json::to_string(&result).unwrap())
+}
 
-    if !output.status.success() {
-        println!("Error: {}", String::from_utf8_lossy(&output.stderr));
-    }
+/** The smallest set of owners which covers every changed file of the branch with files each one covers */
+#[tauri::command(async)]
+fn get_minimal_reviewers_for_branch(
+    abs_repo_path: &str,
+    branch: &str,
+    codeowners_revision: Option<branch_diff::CodeownersRevision>,
+) -> Result<String, String> {
+    let result = reviewers::get_minimal_reviewers(
+        abs_repo_path,
+        branch,
+        codeowners_revision.unwrap_or_default(),
+    )?;
+    Ok(serde_json::to_string(&result).unwrap())
+}
 
-    let mut branch_files: Vec<String> = Vec::new();
-    for file_path in String::from_utf8_lossy(&output.stdout)
-        .to_string()
-        .split("\n")
-    {
-        // it is for latest line
-        if !file_path.is_empty() {
-            branch_files.push(file_path.to_string());
-        }
-    }
-    branch_files
+/**
+ * Changed files of the branch which still lack approval of their owners.
+ * Approvers are read from `Approved-by` trailers of branch commits if they are not passed.
+ */
+#[tauri::command(async)]
+fn simulate_approvals_for_branch(
+    abs_repo_path: &str,
+    branch: &str,
+    codeowners_revision: Option<branch_diff::CodeownersRevision>,
+    roster_path: Option<&str>,
+    approvers: Option<Vec<String>>,
+    gitlab_sections: Option<bool>,
+) -> Result<String, String> {
+    let roster = match roster_path {
+        Some(roster_path) => Roster::load(abs_repo_path, roster_path)?,
+        None => Roster::default(),
+    };
+    let result = approvals::simulate_branch_approvals(
+        abs_repo_path,
+        branch,
+        codeowners_revision.unwrap_or_default(),
+        &roster,
+        approvers,
+        gitlab_sections.unwrap_or_default(),
+    )?;
+    Ok(serde_json::to_string(&result).unwrap())
 }
 
-/** Returns comments for codeowners file of passed branch */
-fn get_codeowners_content(abs_repo_path: &str, branch: &str) -> String {
-    let output = Command::new("git")
-        .current_dir(abs_repo_path)
-        .arg("--no-pager")
-        .arg("show")
-        .arg(format!("{branch}:CODEOWNERS"))
-        .output()
-        .expect("git command failed");
-    if !output.status.success() {
-        println!("Error: {}", String::from_utf8_lossy(&output.stderr));
-    }
-    let content: String = String::from_utf8_lossy(&output.stdout).to_string();
-    content
+/** Markdown summary of changed files ownership for pull request description */
+#[tauri::command(async)]
+fn get_pull_request_summary_for_branch(
+    abs_repo_path: &str,
+    branch: &str,
+    codeowners_revision: Option<branch_diff::CodeownersRevision>,
+    template_path: Option<&str>,
+) -> Result<String, String> {
+    pr_summary::get_pull_request_summary(
+        abs_repo_path,
+        branch,
+        codeowners_revision.unwrap_or_default(),
+        template_path,
+    )
 }
 
-fn get_joined_codeowners(
-    owners_vec: Option<&Vec<codeowners_file_parser::Owner>>,
-) -> Option<String> {
-    match owners_vec {
-        None => None,
-        Some(owners) => Some(
-            owners
-                .iter()
-                .map(|owner| format!("{owner}"))
-                .collect::<Vec<String>>()
-                .join(", "),
-        ),
-    }
+/** Writes owners of every file of the branch passing the filter to the output file */
+#[tauri::command(async)]
+fn export_all_codeowners_for_branch(
+    abs_repo_path: &str,
+    branch: &str,
+    format: export::ExportFormat,
+    output_path: &str,
+    filter: Option<export::ExportFilter>,
+) -> Result<String, String> {
+    let filter = filter.unwrap_or_default();
+    let result = export::export_to_file(output_path, |writer| {
+        export::write_all_owners(writer, abs_repo_path, branch, format, &filter)
+    })?;
+    Ok(serde_json::to_string(&result).unwrap())
 }
 
-#[derive(Serialize, Clone)]
-struct AllCodeownersProgressPayload {
-    files_handled: u32,
-    files_total: u32,
-    session_id: String,
+/** Writes owners of files changed in the branch to the output file */
+#[tauri::command(async)]
+fn export_changed_codeowners_for_branch(
+    abs_repo_path: &str,
+    branch: &str,
+    codeowners_revision: Option<branch_diff::CodeownersRevision>,
+    format: export::ExportFormat,
+    output_path: &str,
+) -> Result<String, String> {
+    let revision = codeowners_revision.unwrap_or_default();
+    let result = export::export_to_file(output_path, |writer| {
+        export::write_branch_changes(writer, abs_repo_path, branch, revision, format)
+    })?;
+    Ok(serde_json::to_string(&result).unwrap())
 }
 
-struct FrontendCodeowner {
-    /// Codeowners
-    owners: String,
-    files: Vec<String>,
+/** Files, bytes and lines of the branch per owner, per extension and per owner and extension */
+#[tauri::command(async)]
+fn get_repo_statistics_for_branch(abs_repo_path: &str, branch: &str) -> String {
+    let result = statistics::get_repo_statistics(abs_repo_path, branch);
+    serde_json::to_string(&result).unwrap()
 }
 
-impl Serialize for FrontendCodeowner {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        // 2 is the number of fields in the struct.
-        let mut state = serializer.serialize_struct("FrontendCodeowner", 2)?;
-        state.serialize_field("owners", &self.owners)?;
-        state.serialize_field("files", &self.files)?;
-        state.end()
+/** Owned share of files, bytes and lines per directory. Markdown is returned instead of JSON if requested */
+#[tauri::command(async)]
+fn get_coverage_report_for_branch(
+    abs_repo_path: &str,
+    branch: &str,
+    depth: Option<usize>,
+    top: Option<usize>,
+    markdown: Option<bool>,
+) -> String {
+    let result = coverage::get_coverage_report(
+        abs_repo_path,
+        branch,
+        depth.unwrap_or(coverage::DEFAULT_DEPTH),
+        top.unwrap_or(coverage::DEFAULT_TOP),
+    );
+    if markdown.unwrap_or_default() {
+        coverage::coverage_markdown(&result)
+    } else {
+        serde_json::to_string(&result).unwrap()
     }
 }
 
-fn get_all_codeowners_for_branch_struct(
-    app_handle: tauri::AppHandle,
+/** Coverage and count of owners of commits of the range sampled once per interval, oldest first */
+#[tauri::command(async)]
+fn get_coverage_trend(
+    abs_repo_path: &str,
+    range: &str,
+    interval_days: Option<u32>,
+) -> Result<String, String> {
+    let result = coverage_trend::get_coverage_trend(
+        abs_repo_path,
+        range,
+        interval_days.unwrap_or(coverage_trend::DEFAULT_INTERVAL_DAYS),
+    )?;
+    Ok(serde_json::to_string(&result).unwrap())
+}
+
+/** Suggested owners and CODEOWNERS lines for unowned paths from recent authors and owners of neighbours */
+#[tauri::command(async)]
+fn get_owner_suggestions_for_branch(
     abs_repo_path: &str,
     branch: &str,
-    session_id: &str,
-) -> DirectoryOwners {
-    let codeowners_content = get_codeowners_content(abs_repo_path, branch);
-    let codeowners = codeowners_file_parser::from_reader(codeowners_content.as_bytes());
-    let files = get_branch_files_vector(abs_repo_path, branch);
-    let mut result: DirectoryOwners = DirectoryOwners {
-        name: String::from(""),
-        directories: vec![],
-        files: vec![],
-        owner: Option::None,
+    months: Option<u32>,
+    max_authors: Option<usize>,
+    roster_path: Option<&str>,
+) -> Result<String, String> {
+    let roster = match roster_path {
+        Some(roster_path) => Roster::load(abs_repo_path, roster_path)?,
+        None => Roster::default(),
     };
+    let result = owner_suggestions::get_owner_suggestions(
+        abs_repo_path,
+        branch,
+        months.unwrap_or(owner_suggestions::DEFAULT_MONTHS),
+        max_authors.unwrap_or(owner_suggestions::DEFAULT_MAX_AUTHORS),
+        &roster,
+    );
+    Ok(serde_json::to_string(&result).unwrap())
+}
 
-    for (file_index, file_path) in files.iter().enumerate() {
-        if file_index % 100 == 0 {
-            let payload = AllCodeownersProgressPayload {
-                files_handled: file_index as u32,
-                files_total: files.len() as u32,
-                session_id: session_id.to_string(),
-            };
-            app_handle
-                .emit_all("all-codeowners-progress", payload)
-                .unwrap();
-            println!("handled {file_index} from {}", files.len());
-        }
-        // if file_index > 2000 {
-        //     break; // TODO: we need speedup algorithm. Now it is too long. Uncomment for quick debugging.
-        // }
-        let owner = get_joined_codeowners(codeowners.of(&file_path)).unwrap_or(String::new());
-        let mut current = &mut result;
-        let mut it = file_path.split('/').peekable();
-        while let Some(file_path_part) = it.next() {
-            let is_last_part = it.peek().is_none();
-            if is_last_part {
-                current.files.push(FileOwners {
-                    name: file_path_part.to_string(),
-                    owner: String::from(&owner),
-                })
-            } else {
-                if let Some(existing_dir_position) = current
-                    .directories
-                    .iter()
-                    .position(|dir| dir.name == file_path_part)
-                {
-                    current = &mut current.directories[existing_dir_position];
-                } else {
-                    let new_dir_owners = DirectoryOwners {
-                        name: String::from(file_path_part),
-                        directories: vec![],
-                        files: vec![],
-                        owner: Option::None,
-                    };
-                    current.directories.push(new_dir_owners);
-                    current = current
-                        .directories
-                        .iter_mut()
-                        .find(|dir| dir.name == file_path_part)
-                        .expect("Just added this directory, so it should be found")
-                }
-            }
-        }
-    }
-    result
+/** Declared owners of every rule compared with authors who changed its files in the last months */
+#[tauri::command(async)]
+fn get_ownership_drift_for_branch(
+    abs_repo_path: &str,
+    branch: &str,
+    months: Option<u32>,
+    roster_path: Option<&str>,
+) -> Result<String, String> {
+    let roster = match roster_path {
+        Some(roster_path) => Roster::load(abs_repo_path, roster_path)?,
+        None => Roster::default(),
+    };
+    let result = drift::get_ownership_drift(
+        abs_repo_path,
+        branch,
+        months.unwrap_or(drift::DEFAULT_MONTHS),
+        &roster,
+    );
+    Ok(serde_json::to_string(&result).unwrap())
 }
 
-struct FileOwners {
-    name: String,
-    owner: String,
+/** Contributors, top contributor share and bus factor per owner and per directory from recent history */
+#[tauri::command(async)]
+fn get_knowledge_report_for_branch(
+    abs_repo_path: &str,
+    branch: &str,
+    months: Option<u32>,
+    depth: Option<usize>,
+    roster_path: Option<&str>,
+) -> Result<String, String> {
+    let roster = match roster_path {
+        Some(roster_path) => Roster::load(abs_repo_path, roster_path)?,
+        None => Roster::default(),
+    };
+    let result = knowledge::get_knowledge_report(
+        abs_repo_path,
+        branch,
+        months.unwrap_or(knowledge::DEFAULT_MONTHS),
+        depth.unwrap_or(coverage::DEFAULT_DEPTH),
+        &roster,
+    );
+    Ok(serde_json::to_string(&result).unwrap())
 }
 
-impl Serialize for FileOwners {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        // 2 is the number of fields in the struct.
-        let mut state = serializer.serialize_struct("FileOwners", 2)?;
-        state.serialize_field("name", &self.name)?;
-        state.serialize_field("owner", &self.owner)?;
-        state.end()
-    }
+/** Commits and changed lines per owner group and per directory of recent history for the tree heatmap */
+#[tauri::command(async)]
+fn get_churn_report_for_branch(
+    abs_repo_path: &str,
+    branch: &str,
+    months: Option<u32>,
+    depth: Option<usize>,
+) -> String {
+    let result = churn::get_churn_report(
+        abs_repo_path,
+        branch,
+        months.unwrap_or(churn::DEFAULT_MONTHS),
+        depth.unwrap_or(coverage::DEFAULT_DEPTH),
+    );
+    serde_json::to_string(&result).unwrap()
 }
 
-struct DirectoryOwners {
-    /** Directory name. For root folder it is empty */
-    name: String,
-    directories: Vec<DirectoryOwners>,
-    files: Vec<FileOwners>,
-    /**
-     * @return string which contains all owners in case every files/directories inside have their own owners.
-     *   null in other case (for root directory also null)
-     */
-    owner: Option<String>,
+/** Intervals of owners of a single file across commits of the branch which changed CODEOWNERS */
+#[tauri::command(async)]
+fn get_file_ownership_history(
+    abs_repo_path: &str,
+    branch: &str,
+    file_path: &str,
+) -> Result<String, String> {
+    let result = ownership_history::get_ownership_history(abs_repo_path, branch, file_path)?;
+    Ok(serde_json::to_string(&result).unwrap())
 }
 
-impl Serialize for DirectoryOwners {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        // 4 is the number of fields in the struct.
-        let mut state = serializer.serialize_struct("DirectoryOwners", 4)?;
-        state.serialize_field("name", &self.name)?;
-        state.serialize_field("directories", &self.directories)?;
-        state.serialize_field("files", &self.files)?;
-        state.serialize_field("owner", &self.owner)?;
-        state.end()
-    }
+#[derive(Serialize, Clone)]
+struct AllCodeownersProgressPayload {
+    files_handled: u32,
+    files_total: u32,
+    session_id: String,
 }
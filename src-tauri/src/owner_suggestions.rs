@@ -0,0 +1,390 @@
+//! Suggested owners of unowned files and directories from recent authors and owners of neighbours
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::all_owners::{self, DirectoryOwners};
+use crate::coverage;
+use crate::git::{self, CommitChanges};
+use crate::identity::IdentityResolver;
+use crate::roster::Roster;
+use codeowners::Owner;
+
+/** History window used by default */
+pub const DEFAULT_MONTHS: u32 = 12;
+/** Count of authors added to owners of a suggested line by default */
+pub const DEFAULT_MAX_AUTHORS: usize = 2;
+/** Weight of a change halves every this count of days */
+const HALF_LIFE_DAYS: f64 = 90.0;
+const SECONDS_PER_DAY: f64 = 24.0 * 60.0 * 60.0;
+/** GitHub reads them as wildcards, comments or negation and has no escape for them */
+const UNESCAPABLE_CHARACTERS: [char; 4] = ['#', '*', '?', '!'];
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct AuthorScore {
+    /** Username if it is known, email otherwise */
+    pub author: String,
+    pub commits: usize,
+    /** Added and deleted lines */
+    pub lines: u64,
+    /** Sum of changed lines weighted by recency, rounded to two decimals */
+    pub score: f64,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct OwnerSuggestion {
+    /** Unowned file, or directory with trailing slash whose every file is unowned. `/` is the whole repository */
+    pub path: String,
+    /** Recent authors of the path, the highest score first */
+    pub authors: Vec<AuthorScore>,
+    /** The closest directory which has owned files. Null if nothing is owned around */
+    pub nearest_path: Option<String>,
+    /** The most common owners of files of the nearest directory */
+    pub nearest_owners: Vec<Owner>,
+    /** Nearest owners followed by the top authors who are not covered by them */
+    pub suggested_owners: Vec<String>,
+    /** CODEOWNERS line with suggested owners. Null if there is nobody to suggest or no pattern */
+    pub line: Option<String>,
+    /** Why there is no line though owners are suggested, the pattern has to be written by hand */
+    pub warning: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct OwnerSuggestions {
+    pub branch: String,
+    pub months: u32,
+    pub suggestions: Vec<OwnerSuggestion>,
+    /** Lines ready to be appended to CODEOWNERS */
+    pub codeowners_lines: Vec<String>,
+}
+
+/** Suggestions for every unowned subtree of the branch from history of the last `months` */
+pub fn get_owner_suggestions(
+    abs_repo_path: &str,
+    branch: &str,
+    months: u32,
+    max_authors: usize,
+    roster: &Roster,
+) -> OwnerSuggestions {
+    let root = all_owners::get_all_codeowners_for_branch_struct(abs_repo_path, branch, |_, _| {});
+    let history = git::get_commit_changes(abs_repo_path, branch, &format!("{months} months ago"));
+    let identities = IdentityResolver::load(abs_repo_path, roster.clone());
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() as i64);
+    let suggestions = owner_suggestions(&root, &history, &identities, roster, now, max_authors);
+    OwnerSuggestions {
+        branch: branch.to_string(),
+        months,
+        codeowners_lines: suggestions
+            .iter()
+            .filter_map(|suggestion| suggestion.line.clone())
+            .collect(),
+        suggestions,
+    }
+}
+
+pub fn owner_suggestions(
+    root: &DirectoryOwners,
+    history: &[CommitChanges],
+    identities: &IdentityResolver,
+    roster: &Roster,
+    now: i64,
+    max_authors: usize,
+) -> Vec<OwnerSuggestion> {
+    let mut paths = coverage::unowned_paths(root);
+    paths.sort();
+    let index_by_path: HashMap<&str, usize> = paths
+        .iter()
+        .enumerate()
+        .map(|(index, path)| (path.as_str(), index))
+        .collect();
+    // scores of authors per unowned path: author -> (commits, lines, score)
+    let mut scores: Vec<BTreeMap<String, (usize, u64, f64)>> = vec![BTreeMap::new(); paths.len()];
+    for changes in history {
+        let identity =
+            identities.resolve(&changes.commit.author_name, &changes.commit.author_email);
+        let author = identity.username.unwrap_or(identity.email);
+        let age_days = (now - changes.commit.timestamp).max(0) as f64 / SECONDS_PER_DAY;
+        let recency = 0.5_f64.powf(age_days / HALF_LIFE_DAYS);
+        let mut touched: Vec<usize> = Vec::new();
+        for file in &changes.files {
+            let lines = u64::from(file.added_lines) + u64::from(file.deleted_lines);
+            for index in containing_paths(&file.path, &index_by_path) {
+                let entry = scores[index].entry(author.clone()).or_default();
+                entry.1 += lines;
+                // binary files and empty changes still count as some work
+                entry.2 += lines.max(1) as f64 * recency;
+                if !touched.contains(&index) {
+                    touched.push(index);
+                    entry.0 += 1;
+                }
+            }
+        }
+    }
+
+    paths
+        .iter()
+        .zip(scores)
+        .map(|(path, scores)| {
+            let mut authors: Vec<AuthorScore> = scores
+                .into_iter()
+                .map(|(author, (commits, lines, score))| AuthorScore {
+                    author,
+                    commits,
+                    lines,
+                    score: (score * 100.0).round() / 100.0,
+                })
+                .collect();
+            authors.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+            let (nearest_path, nearest_owners) = match nearest_owners(root, path) {
+                Some((nearest_path, owners)) => (Some(nearest_path), owners),
+                None => (None, vec![]),
+            };
+            let mut suggested_owners: Vec<String> = nearest_owners
+                .iter()
+                .map(|owner| owner.to_string())
+                .collect();
+            let top_authors: Vec<String> = authors
+                .iter()
+                .filter(|author| {
+                    !nearest_owners
+                        .iter()
+                        .any(|owner| roster.is_owner(owner, &author.author))
+                })
+                .take(max_authors)
+                .map(|author| author.author.clone())
+                .collect();
+            suggested_owners.extend(top_authors);
+            let pattern = codeowners_pattern(path);
+            let line = match &pattern {
+                Some(pattern) if !suggested_owners.is_empty() => {
+                    Some(format!("{pattern} {}", suggested_owners.join(" ")))
+                }
+                _ => None,
+            };
+            let warning = match pattern {
+                None if !suggested_owners.is_empty() => Some(format!(
+                    "path has one of {} which CODEOWNERS can't escape",
+                    UNESCAPABLE_CHARACTERS
+                        .iter()
+                        .map(|character| format!("'{character}'"))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                )),
+                _ => None,
+            };
+            OwnerSuggestion {
+                path: path.clone(),
+                authors,
+                nearest_path,
+                nearest_owners,
+                suggested_owners,
+                line,
+                warning,
+            }
+        })
+        .collect()
+}
+
+/** Indexes of unowned paths which contain the file: the file itself, its directories or the whole repository */
+fn containing_paths(path: &str, index_by_path: &HashMap<&str, usize>) -> Vec<usize> {
+    let mut candidates: Vec<&str> = vec!["/", path];
+    candidates.extend(
+        path.match_indices('/')
+            .map(|(position, _)| &path[..position + 1]),
+    );
+    candidates
+        .into_iter()
+        .filter_map(|candidate| index_by_path.get(candidate).copied())
+        .collect()
+}
+
+/**
+ * The closest directory above the path which has owned files and the most common owners of them.
+ * Ties go to owners which are sorted first
+ */
+fn nearest_owners(root: &DirectoryOwners, path: &str) -> Option<(String, Vec<Owner>)> {
+    let parts: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+    // directories from the root to the parent of the path
+    let mut chain: Vec<(String, &DirectoryOwners)> = vec![(String::new(), root)];
+    for part in &parts[..parts.len().saturating_sub(1)] {
+        let (parent_path, parent) = chain.last().unwrap();
+        let directory = parent.directories.iter().find(|dir| dir.name == *part)?;
+        chain.push((format!("{parent_path}{part}/"), directory));
+    }
+    chain
+        .into_iter()
+        .rev()
+        .find_map(|(directory_path, directory)| {
+            let mut counts: BTreeMap<&Vec<Owner>, usize> = BTreeMap::new();
+            count_owners(directory, &mut counts);
+            let (owners, _) = counts.into_iter().fold(
+                None,
+                |best: Option<(&Vec<Owner>, usize)>, (owners, count)| match best {
+                    Some((_, best_count)) if best_count >= count => best,
+                    _ => Some((owners, count)),
+                },
+            )?;
+            let directory_path = if directory_path.is_empty() {
+                String::from("/")
+            } else {
+                directory_path
+            };
+            Some((directory_path, owners.clone()))
+        })
+}
+
+fn count_owners<'a>(directory: &'a DirectoryOwners, counts: &mut BTreeMap<&'a Vec<Owner>, usize>) {
+    for file in &directory.files {
+        if !file.owners.is_empty() {
+            *counts.entry(&file.owners).or_default() += 1;
+        }
+    }
+    for child in &directory.directories {
+        count_owners(child, counts);
+    }
+}
+
+/**
+ * Anchored CODEOWNERS pattern of the path. Spaces and brackets are escaped,
+ * None if the path has characters which can't be escaped
+ */
+fn codeowners_pattern(path: &str) -> Option<String> {
+    if path == "/" {
+        return Some(String::from("*"));
+    }
+    if path.contains(UNESCAPABLE_CHARACTERS) {
+        return None;
+    }
+    let escaped = path
+        .replace(' ', "\\ ")
+        .replace('[', "\\[")
+        .replace(']', "\\]");
+    Some(format!("/{escaped}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::all_owners::FileOwners;
+    use crate::git::{Commit, FileChange};
+    use crate::identity::parse_mailmap;
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    fn directory(
+        name: &str,
+        files: Vec<(&str, &str)>,
+        directories: Vec<DirectoryOwners>,
+    ) -> DirectoryOwners {
+        DirectoryOwners {
+            name: name.into(),
+            directories,
+            files: files
+                .into_iter()
+                .map(|(name, owner)| FileOwners {
+                    name: name.into(),
+                    owner: owner.into(),
+                    owners: owner
+                        .split(", ")
+                        .filter(|owner| !owner.is_empty())
+                        .map(|owner| owner.parse().unwrap())
+                        .collect(),
+                })
+                .collect(),
+            owner: None,
+        }
+    }
+
+    fn commit(email: &str, days_ago: i64, files: Vec<(&str, u32)>) -> CommitChanges {
+        CommitChanges {
+            commit: Commit {
+                sha: String::from("abc"),
+                author_name: String::from("Someone"),
+                author_email: email.into(),
+                timestamp: 1000 * DAY - days_ago * DAY,
+                subject: String::new(),
+            },
+            files: files
+                .into_iter()
+                .map(|(path, lines)| FileChange {
+                    path: path.into(),
+                    added_lines: lines,
+                    deleted_lines: 0,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn suggests_neighbour_owners_and_recent_authors() {
+        let root = directory(
+            "",
+            vec![("README.md", "")],
+            vec![directory(
+                "src",
+                vec![
+                    ("a.rs", "@org/core"),
+                    ("b.rs", "@org/core"),
+                    ("c.rs", "@alice"),
+                ],
+                vec![directory("gen", vec![("x.rs", ""), ("y.rs", "")], vec![])],
+            )],
+        );
+        let roster: Roster =
+            serde_json::from_str(r#"{ "teams": { "@org/core": ["@carol"] } }"#).unwrap();
+        let identities = IdentityResolver::new(
+            parse_mailmap("<bob@example.com> <bob@laptop>\n"),
+            roster.clone(),
+        );
+        let history = vec![
+            commit("bob@laptop", 0, vec![("src/gen/x.rs", 10)]),
+            // old changes weigh less even with more lines
+            commit("dave@example.com", 360, vec![("src/gen/y.rs", 100)]),
+            commit(
+                "1+carol@users.noreply.github.com",
+                10,
+                vec![("src/gen/x.rs", 50)],
+            ),
+            commit("bob@example.com", 5, vec![("src/a.rs", 5)]),
+        ];
+        let suggestions = owner_suggestions(&root, &history, &identities, &roster, 1000 * DAY, 1);
+        assert_eq!(
+            suggestions
+                .iter()
+                .map(|suggestion| suggestion.line.as_deref())
+                .collect::<Vec<_>>(),
+            vec![
+                Some("/README.md @org/core"),
+                Some("/src/gen/ @org/core bob@example.com"),
+            ]
+        );
+        let generated = &suggestions[1];
+        assert_eq!(generated.path, "src/gen/");
+        assert_eq!(generated.nearest_path.as_deref(), Some("src/"));
+        assert_eq!(
+            generated
+                .authors
+                .iter()
+                .map(|author| author.author.as_str())
+                .collect::<Vec<_>>(),
+            vec!["@carol", "bob@example.com", "dave@example.com"]
+        );
+        assert_eq!(suggestions[0].nearest_path.as_deref(), Some("/"));
+        assert!(suggestions[0].authors.is_empty());
+    }
+
+    #[test]
+    fn escaped_patterns_are_parsed_back() {
+        let line = format!("{} @alice\n", codeowners_pattern("my docs/[id]/").unwrap());
+        assert_eq!(line, "/my\\ docs/\\[id\\]/ @alice\n");
+        let codeowners = codeowners::from_reader(line.as_bytes());
+        assert!(codeowners.of("my docs/[id]/page.tsx").is_some());
+        assert!(codeowners.of("my docs/i/page.tsx").is_none());
+        for path in ["notes#1.md", "src/*.rs", "what?.md", "!important/"] {
+            assert_eq!(codeowners_pattern(path), None);
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! Timeline of owners of a single file across commits which changed CODEOWNERS
+use serde::Serialize;
+
+use crate::codeowners_diff::RuleRef;
+use crate::coverage_trend::{self, HistoryCommit};
+use crate::git;
+use crate::normalize_owners;
+use codeowners::{Owner, Owners};
+
+/** Period when the file had the same owners by the same rule pattern */
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct OwnershipInterval {
+    /** Commit which started the interval */
+    pub commit: String,
+    /** Committer date as `YYYY-MM-DD` */
+    pub date: String,
+    /** Committer date as unix timestamp in seconds */
+    pub timestamp: i64,
+    /** Commit which ended the interval. Null for the current interval */
+    pub until_commit: Option<String>,
+    pub until_date: Option<String>,
+    /** Empty if the file was unowned */
+    pub owners: Vec<Owner>,
+    /** CODEOWNERS location at the start of the interval. Null if there was none */
+    pub codeowners_path: Option<String>,
+    /** Rule as it was at the start of the interval. Null if no rule matched */
+    pub rule: Option<RuleRef>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct OwnershipHistory {
+    pub branch: String,
+    pub path: String,
+    /** Oldest first. Empty if CODEOWNERS never existed in any location */
+    pub intervals: Vec<OwnershipInterval>,
+}
+
+/**
+ * Owners of the path at every first-parent commit of the branch which changed any CODEOWNERS location.
+ * Every revision uses the file GitHub would pick, so moving CODEOWNERS into `.github/` is followed.
+ * The path doesn't have to exist, so owners of deleted and future files can be checked too.
+ * Fails if git can't read the branch
+ */
+pub fn get_ownership_history(
+    abs_repo_path: &str,
+    branch: &str,
+    path: &str,
+) -> Result<OwnershipHistory, String> {
+    let mut args = vec![
+        "log",
+        "--first-parent",
+        "--reverse",
+        "--format=%H%x1f%ct%x1f%cs",
+        branch,
+        "--",
+    ];
+    args.extend(git::CODEOWNERS_LOCATIONS);
+    let output = git::try_run_git(abs_repo_path, &args)?;
+    let revisions: Vec<Revision> = coverage_trend::parse_history(&output)
+        .into_iter()
+        .map(
+            |commit| match git::get_github_codeowners(abs_repo_path, &commit.sha) {
+                Some((location, content)) => (
+                    commit,
+                    Some(location),
+                    codeowners::from_reader(content.as_bytes()),
+                ),
+                // deleting the last CODEOWNERS makes the file unowned
+                None => (commit, None, codeowners::from_reader("".as_bytes())),
+            },
+        )
+        .collect();
+    Ok(OwnershipHistory {
+        branch: branch.to_string(),
+        path: path.to_string(),
+        intervals: ownership_intervals(path, &revisions),
+    })
+}
+
+/** Commit which changed CODEOWNERS with location and content of CODEOWNERS after it */
+pub type Revision = (HistoryCommit, Option<&'static str>, Owners);
+
+/** Joins consecutive revisions where owners and the rule pattern stay the same. Revisions are oldest first */
+pub fn ownership_intervals(path: &str, revisions: &[Revision]) -> Vec<OwnershipInterval> {
+    let mut intervals: Vec<OwnershipInterval> = Vec::new();
+    for (commit, location, codeowners) in revisions {
+        let rule = codeowners.rule_of(path);
+        let owners = normalize_owners(rule.map(|rule| &rule.owners));
+        let rule = rule.map(RuleRef::from);
+        if let Some(last) = intervals.last_mut() {
+            let same_pattern = last.rule.as_ref().map(|rule| &rule.pattern)
+                == rule.as_ref().map(|rule| &rule.pattern);
+            if last.owners == owners && same_pattern {
+                continue;
+            }
+            last.until_commit = Some(commit.sha.clone());
+            last.until_date = Some(commit.date.clone());
+        }
+        intervals.push(OwnershipInterval {
+            commit: commit.sha.clone(),
+            date: commit.date.clone(),
+            timestamp: commit.timestamp,
+            until_commit: None,
+            until_date: None,
+            owners,
+            codeowners_path: location.map(String::from),
+            rule,
+        });
+    }
+    intervals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joins_revisions_with_the_same_owners() {
+        let revision = |sha: &str, content: &str| {
+            (
+                HistoryCommit {
+                    sha: sha.into(),
+                    timestamp: 0,
+                    date: format!("2024-01-0{}", sha.len()),
+                },
+                Some("CODEOWNERS"),
+                codeowners::from_reader(content.as_bytes()),
+            )
+        };
+        let revisions = vec![
+            revision("a", "* @alice\n"),
+            // a new unrelated rule shifts the line, but owners stay the same
+            revision("bb", "/docs/ @bob\n* @alice\n"),
+            revision("ccc", "/docs/ @bob\n* @alice\n/src/ @org/core\n"),
+            revision("dddd", "/docs/ @bob\n"),
+        ];
+        let intervals = ownership_intervals("src/main.rs", &revisions);
+        assert_eq!(
+            intervals
+                .iter()
+                .map(|interval| (
+                    interval.commit.as_str(),
+                    interval.until_commit.as_deref(),
+                    interval.rule.as_ref().map(|rule| rule.line)
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("a", Some("ccc"), Some(1)),
+                ("ccc", Some("dddd"), Some(3)),
+                ("dddd", None, None)
+            ]
+        );
+        assert_eq!(intervals[1].owners, vec![Owner::Team("@org/core".into())]);
+        assert_eq!(intervals[0].until_date.as_deref(), Some("2024-01-03"));
+        assert!(intervals[2].owners.is_empty());
+    }
+}
@@ -0,0 +1,204 @@
+//! Files owned by one person directly or through teams of the roster
+use serde::Serialize;
+
+use crate::branch_diff::{
+    self, BranchCodeownersFiles, CodeownersRevision, FrontendChangedFile, FrontendCodeowner,
+    OwnersGrouping,
+};
+use crate::git;
+use crate::normalize_owners;
+use crate::roster::Roster;
+use codeowners::{Owner, Owners};
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ReviewFile {
+    #[serde(flatten)]
+    pub file: FrontendChangedFile,
+    /** Owners of the file which include the person: username itself or teams of the person */
+    pub via: Vec<Owner>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct PersonalReview {
+    pub person: String,
+    pub files: Vec<ReviewFile>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct OwnedFile {
+    pub path: String,
+    /** Owners of the file which include the person: username itself or teams of the person */
+    pub via: Vec<Owner>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct PersonalOwnership {
+    pub person: String,
+    /** Count of all files of the branch */
+    pub files_total: usize,
+    pub files: Vec<OwnedFile>,
+}
+
+/**
+ * Changed files of the branch which need review of the person.
+ * Owners are resolved the same way as `get_changed_codeowners` does with requested revision.
+ */
+pub fn get_review_files(
+    abs_repo_path: &str,
+    branch: &str,
+    revision: CodeownersRevision,
+    roster: &Roster,
+    person: &str,
+) -> Result<PersonalReview, String> {
+    let codeowners = BranchCodeownersFiles::load(abs_repo_path, branch)?;
+    let changes = branch_diff::get_branch_changes(abs_repo_path, branch)?;
+    let groups: Vec<FrontendCodeowner> = codeowners
+        .base_groups(&changes, revision, OwnersGrouping::Individual)
+        .into_iter()
+        .chain(codeowners.head_groups(&changes, revision, OwnersGrouping::Individual))
+        .flatten()
+        .collect();
+    Ok(PersonalReview {
+        person: person.to_string(),
+        files: review_files(&groups, roster, person),
+    })
+}
+
+/** Collects files of individual owner groups which include the person, every file once */
+pub fn review_files(
+    groups: &[FrontendCodeowner],
+    roster: &Roster,
+    person: &str,
+) -> Vec<ReviewFile> {
+    let mut result: Vec<ReviewFile> = Vec::new();
+    for group in groups {
+        for owner in &group.owners {
+            if !roster.includes(owner, person) {
+                continue;
+            }
+            for file in &group.files {
+                match result
+                    .iter_mut()
+                    .find(|review| review.file.change.path == file.change.path)
+                {
+                    Some(review) => review.via.push(owner.clone()),
+                    None => result.push(ReviewFile {
+                        file: file.clone(),
+                        via: vec![owner.clone()],
+                    }),
+                }
+            }
+        }
+    }
+    for review in result.iter_mut() {
+        review.via.sort();
+        review.via.dedup();
+    }
+    result.sort_by(|a, b| a.file.change.path.cmp(&b.file.change.path));
+    result
+}
+
+/** All files of the branch owned by the person directly or through teams */
+pub fn get_owned_files(
+    abs_repo_path: &str,
+    branch: &str,
+    roster: &Roster,
+    person: &str,
+) -> PersonalOwnership {
+    let codeowners_content = git::get_codeowners_content(abs_repo_path, branch);
+    let codeowners = codeowners::from_reader(codeowners_content.as_bytes());
+    let files = git::get_branch_files_vector(abs_repo_path, branch);
+    PersonalOwnership {
+        person: person.to_string(),
+        files_total: files.len(),
+        files: owned_files(&files, &codeowners, roster, person),
+    }
+}
+
+pub fn owned_files(
+    files: &[String],
+    codeowners: &Owners,
+    roster: &Roster,
+    person: &str,
+) -> Vec<OwnedFile> {
+    files
+        .iter()
+        .filter_map(|file_path| {
+            let via: Vec<Owner> = normalize_owners(codeowners.of(file_path))
+                .into_iter()
+                .filter(|owner| roster.includes(owner, person))
+                .collect();
+            if via.is_empty() {
+                None
+            } else {
+                Some(OwnedFile {
+                    path: file_path.to_string(),
+                    via,
+                })
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::branch_diff::{group_changes_by_owners, parse_name_status};
+
+    const CODEOWNERS: &str = r"* @org/core
+/payments/ @org/payments @alice
+/docs/ @bob
+";
+
+    fn roster() -> Roster {
+        serde_json::from_str(
+            r#"{ "teams": { "@org/payments": ["@alice"], "@org/core": ["@bob"] } }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn review_files_lists_direct_and_team_ownership_once() {
+        let codeowners = codeowners::from_reader(CODEOWNERS.as_bytes());
+        let changes = parse_name_status("M\0payments/api.rs\0M\0docs/a.md\0M\0README.md\0");
+        let groups = group_changes_by_owners(
+            &changes,
+            &codeowners,
+            &codeowners,
+            OwnersGrouping::Individual,
+        );
+        let files = review_files(&groups, &roster(), "@alice");
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].file.change.path, "payments/api.rs");
+        assert_eq!(
+            files[0].via,
+            vec![
+                Owner::Username("@alice".into()),
+                Owner::Team("@org/payments".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn owned_files_uses_team_membership() {
+        let codeowners = codeowners::from_reader(CODEOWNERS.as_bytes());
+        let files = vec![
+            String::from("README.md"),
+            String::from("payments/api.rs"),
+            String::from("docs/a.md"),
+        ];
+        assert_eq!(
+            owned_files(&files, &codeowners, &roster(), "@bob"),
+            vec![
+                OwnedFile {
+                    path: "README.md".into(),
+                    via: vec![Owner::Team("@org/core".into())],
+                },
+                OwnedFile {
+                    path: "docs/a.md".into(),
+                    via: vec![Owner::Username("@bob".into())],
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,282 @@
+//! Markdown summary of branch ownership for pull request descriptions
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+use crate::branch_diff::{
+    self, ChangedFile, CodeownersRevision, FrontendChangedFile, FrontendCodeowner, OwnersGrouping,
+    OwnershipChange,
+};
+use crate::join_owners;
+use crate::reviewers;
+use crate::template;
+use codeowners::Owner;
+
+/** Template used when the repository doesn't provide its own. See `template` module for syntax */
+pub const DEFAULT_TEMPLATE: &str = r"### Code owners
+
+**{{files_text}}** changed, **+{{added_lines}} / -{{deleted_lines}}** lines
+
+{{#reviewers_line}}
+Suggested reviewers: {{reviewers_line}}
+
+{{/reviewers_line}}
+{{#groups}}
+<details>
+<summary><b>{{label}}</b>: {{files_text}}, +{{added_lines}} / -{{deleted_lines}}</summary>
+
+{{#files}}
+- `{{path}}`{{#old_path}} (moved from `{{old_path}}`){{/old_path}} {{lines}}
+{{/files}}
+
+</details>
+{{/groups}}
+{{#unowned_files}}
+{{#first}}
+
+> [!WARNING]
+> {{unowned_text}} without owners:
+{{/first}}
+> - `{{path}}`
+{{/unowned_files}}
+{{#ownership_changed}}
+{{#first}}
+
+Owners changed by this branch:
+{{/first}}
+- `{{path}}`: {{base_label}} → {{head_label}}
+{{/ownership_changed}}
+";
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SummaryFile {
+    #[serde(flatten)]
+    pub change: ChangedFile,
+    /** `+added -deleted` or `binary` */
+    pub lines: String,
+    /** True for the first item of a list, lets templates render list headers once */
+    pub first: bool,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SummaryGroup {
+    pub label: String,
+    pub files_count: usize,
+    /** `1 file` or `N files` */
+    pub files_text: String,
+    pub added_lines: u64,
+    pub deleted_lines: u64,
+    pub files: Vec<SummaryFile>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SummaryOwnershipChange {
+    pub path: String,
+    /** Owners joined with ", ", `unowned` if there are none */
+    pub base_label: String,
+    pub head_label: String,
+    pub first: bool,
+}
+
+/** Data available to summary templates */
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SummaryData {
+    pub branch: String,
+    /** Count of distinct changed paths. Renamed files are listed in several groups but counted once */
+    pub files_count: usize,
+    /** `1 file` or `N files` */
+    pub files_text: String,
+    pub added_lines: u64,
+    pub deleted_lines: u64,
+    /** Minimal set of owners covering all owned files */
+    pub reviewers: Vec<String>,
+    /** Reviewers joined with ", " */
+    pub reviewers_line: String,
+    /** Groups of owned files */
+    pub groups: Vec<SummaryGroup>,
+    pub unowned_count: usize,
+    /** `1 changed file` or `N changed files` */
+    pub unowned_text: String,
+    pub unowned_files: Vec<SummaryFile>,
+    /** Files of the branch whose owners differ between base and branch CODEOWNERS. Empty unless both revisions requested */
+    pub ownership_changed: Vec<SummaryOwnershipChange>,
+}
+
+/**
+ * Renders ownership of changed files of the branch as Markdown.
+ * Groups of branch CODEOWNERS are used unless only base revision is requested,
+ * with both revisions files whose owners are changed by the branch are listed too.
+ * Relative template path is resolved against the repository.
+ */
+pub fn get_pull_request_summary(
+    abs_repo_path: &str,
+    branch: &str,
+    revision: CodeownersRevision,
+    template_path: Option<&str>,
+) -> Result<String, String> {
+    let template = match template_path {
+        Some(template_path) => {
+            let path = Path::new(abs_repo_path).join(template_path);
+            fs::read_to_string(&path)
+                .map_err(|error| format!("Can't read template {}: {error}", path.display()))?
+        }
+        None => DEFAULT_TEMPLATE.to_string(),
+    };
+    let result =
+        branch_diff::get_changed_codeowners(abs_repo_path, branch, revision, OwnersGrouping::Set)?;
+    let groups = result.head.or(result.base).unwrap_or_default();
+    let ownership_changed = match revision {
+        CodeownersRevision::Both => result.ownership_changed,
+        _ => vec![],
+    };
+    let data = summary_data(branch, &groups, &ownership_changed);
+    template::render(&template, &serde_json::to_value(&data).unwrap())
+}
+
+/** `1 file`, `2 files`. Only regular plurals are needed by the default template */
+fn plural(count: usize, singular: &str) -> String {
+    if count == 1 {
+        format!("{count} {singular}")
+    } else {
+        format!("{count} {singular}s")
+    }
+}
+
+fn owners_label(owners: &[Owner]) -> String {
+    if owners.is_empty() {
+        String::from("unowned")
+    } else {
+        join_owners(owners)
+    }
+}
+
+fn summary_files(files: &[FrontendChangedFile]) -> Vec<SummaryFile> {
+    files
+        .iter()
+        .enumerate()
+        .map(|(index, file)| SummaryFile {
+            change: file.change.clone(),
+            lines: if file.change.lines.binary {
+                String::from("binary")
+            } else {
+                format!(
+                    "+{} -{}",
+                    file.change.lines.added_lines, file.change.lines.deleted_lines
+                )
+            },
+            first: index == 0,
+        })
+        .collect()
+}
+
+pub fn summary_data(
+    branch: &str,
+    groups: &[FrontendCodeowner],
+    ownership_changed: &[OwnershipChange],
+) -> SummaryData {
+    let mut changes: Vec<&ChangedFile> = groups
+        .iter()
+        .flat_map(|group| group.files.iter().map(|file| &file.change))
+        .collect();
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes.dedup_by(|a, b| a.path == b.path);
+    let reviewers: Vec<String> =
+        reviewers::minimal_reviewers(&groups.iter().collect::<Vec<&FrontendCodeowner>>())
+            .reviewers
+            .iter()
+            .map(|reviewer| reviewer.reviewer.to_string())
+            .collect();
+    let unowned_files: Vec<SummaryFile> = groups
+        .iter()
+        .filter(|group| group.owners.is_empty())
+        .flat_map(|group| summary_files(&group.files))
+        .collect();
+    SummaryData {
+        branch: branch.to_string(),
+        files_count: changes.len(),
+        files_text: plural(changes.len(), "file"),
+        added_lines: changes
+            .iter()
+            .map(|change| change.lines.added_lines as u64)
+            .sum(),
+        deleted_lines: changes
+            .iter()
+            .map(|change| change.lines.deleted_lines as u64)
+            .sum(),
+        reviewers_line: reviewers.join(", "),
+        reviewers,
+        groups: groups
+            .iter()
+            .filter(|group| !group.owners.is_empty())
+            .map(|group| SummaryGroup {
+                label: group.label.clone(),
+                files_count: group.files.len(),
+                files_text: plural(group.files.len(), "file"),
+                added_lines: group.added_lines,
+                deleted_lines: group.deleted_lines,
+                files: summary_files(&group.files),
+            })
+            .collect(),
+        unowned_count: unowned_files.len(),
+        unowned_text: plural(unowned_files.len(), "changed file"),
+        unowned_files,
+        ownership_changed: ownership_changed
+            .iter()
+            .enumerate()
+            .map(|(index, change)| SummaryOwnershipChange {
+                path: change.path.clone(),
+                base_label: owners_label(&change.base_owners),
+                head_label: owners_label(&change.head_owners),
+                first: index == 0,
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::branch_diff::{get_ownership_changes, group_changes_by_owners, parse_name_status};
+
+    #[test]
+    fn renders_default_template() {
+        let base = codeowners::from_reader("/src/ @org/legacy\n".as_bytes());
+        let codeowners = codeowners::from_reader("/src/ @org/core\n".as_bytes());
+        let mut changes = parse_name_status("M\0src/main.rs\0A\0README.md\0");
+        changes[0].lines.added_lines = 3;
+        changes[0].lines.deleted_lines = 1;
+        let groups =
+            group_changes_by_owners(&changes, &codeowners, &codeowners, OwnersGrouping::Set);
+        let ownership_changed = get_ownership_changes(
+            &[String::from("README.md"), String::from("src/main.rs")],
+            &base,
+            &codeowners,
+        );
+        let data = summary_data("feature", &groups, &ownership_changed);
+        let markdown =
+            template::render(DEFAULT_TEMPLATE, &serde_json::to_value(&data).unwrap()).unwrap();
+        assert_eq!(
+            markdown,
+            r"### Code owners
+
+**2 files** changed, **+3 / -1** lines
+
+Suggested reviewers: @org/core
+
+<details>
+<summary><b>@org/core</b>: 1 file, +3 / -1</summary>
+
+- `src/main.rs` +3 -1
+
+</details>
+
+> [!WARNING]
+> 1 changed file without owners:
+> - `README.md`
+
+Owners changed by this branch:
+- `src/main.rs`: @org/legacy → @org/core
+"
+        );
+    }
+}
@@ -0,0 +1,238 @@
+//! The smallest set of reviewers which covers ownership of every changed file of a branch
+use serde::Serialize;
+
+use crate::branch_diff::{self, CodeownersRevision, FrontendCodeowner, OwnersGrouping};
+use codeowners::Owner;
+
+/** Exact search checks all combinations of candidates, so it is used only for small inputs */
+const EXACT_MAX_CANDIDATES: usize = 20;
+/** Groups are kept as bits of u64 during exact search */
+const EXACT_MAX_GROUPS: usize = 64;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ReviewerFiles {
+    pub reviewer: Owner,
+    /** Changed files which this reviewer owns */
+    pub files: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ReviewerSet {
+    /** True if the set is the smallest possible, false if greedy approximation was used */
+    pub exact: bool,
+    pub reviewers: Vec<ReviewerFiles>,
+    /** Files without owners which nobody can cover */
+    pub unowned_files: Vec<String>,
+}
+
+/** Reviewers for changed files of the branch grouped with requested CODEOWNERS revision */
+pub fn get_minimal_reviewers(
+    abs_repo_path: &str,
+    branch: &str,
+    revision: CodeownersRevision,
+) -> Result<ReviewerSet, String> {
+    let result =
+        branch_diff::get_changed_codeowners(abs_repo_path, branch, revision, OwnersGrouping::Set)?;
+    let groups: Vec<&FrontendCodeowner> = result
+        .base
+        .iter()
+        .chain(result.head.iter())
+        .flatten()
+        .collect();
+    Ok(minimal_reviewers(&groups))
+}
+
+/**
+ * Every owner set group needs approval of any one of its owners.
+ * Chooses the fewest owners so that every group has at least one of them.
+ */
+pub fn minimal_reviewers(groups: &[&FrontendCodeowner]) -> ReviewerSet {
+    let (unowned, owned): (Vec<&FrontendCodeowner>, Vec<&FrontendCodeowner>) =
+        groups.iter().partition(|group| group.owners.is_empty());
+    let mut candidates: Vec<&Owner> = owned.iter().flat_map(|group| &group.owners).collect();
+    candidates.sort();
+    candidates.dedup();
+    let covers: Vec<Vec<bool>> = candidates
+        .iter()
+        .map(|candidate| {
+            owned
+                .iter()
+                .map(|group| group.owners.contains(candidate))
+                .collect()
+        })
+        .collect();
+
+    let exact = candidates.len() <= EXACT_MAX_CANDIDATES && owned.len() <= EXACT_MAX_GROUPS;
+    let chosen = if exact {
+        exact_cover(&covers, owned.len())
+    } else {
+        let files_counts: Vec<usize> = candidates
+            .iter()
+            .map(|candidate| files_of(&owned, candidate).len())
+            .collect();
+        greedy_cover(&covers, &files_counts, owned.len())
+    };
+
+    ReviewerSet {
+        exact,
+        reviewers: chosen
+            .into_iter()
+            .map(|index| ReviewerFiles {
+                reviewer: candidates[index].clone(),
+                files: files_of(&owned, candidates[index]),
+            })
+            .collect(),
+        unowned_files: paths(unowned.iter().flat_map(|group| &group.files)),
+    }
+}
+
+fn files_of(groups: &[&FrontendCodeowner], owner: &Owner) -> Vec<String> {
+    paths(
+        groups
+            .iter()
+            .filter(|group| group.owners.contains(owner))
+            .flat_map(|group| &group.files),
+    )
+}
+
+fn paths<'a, I>(files: I) -> Vec<String>
+where
+    I: Iterator<Item = &'a branch_diff::FrontendChangedFile>,
+{
+    let mut paths: Vec<String> = files.map(|file| file.change.path.clone()).collect();
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+/** Checks combinations from the smallest size, the first combination in candidates order wins */
+fn exact_cover(covers: &[Vec<bool>], groups_count: usize) -> Vec<usize> {
+    let masks: Vec<u64> = covers
+        .iter()
+        .map(|cover| {
+            cover
+                .iter()
+                .enumerate()
+                .filter(|(_, covered)| **covered)
+                .fold(0, |mask, (group, _)| mask | 1 << group)
+        })
+        .collect();
+    let full: u64 = if groups_count == 0 {
+        0
+    } else {
+        u64::MAX >> (64 - groups_count)
+    };
+    (0..=masks.len())
+        .find_map(|size| {
+            let mut chosen: Vec<usize> = Vec::new();
+            if find_combination(&masks, full, size, 0, 0, &mut chosen) {
+                Some(chosen)
+            } else {
+                None
+            }
+        })
+        .unwrap_or_default()
+}
+
+fn find_combination(
+    masks: &[u64],
+    full: u64,
+    size: usize,
+    start: usize,
+    covered: u64,
+    chosen: &mut Vec<usize>,
+) -> bool {
+    if chosen.len() == size {
+        return covered == full;
+    }
+    for index in start..masks.len() {
+        // not enough candidates left to fill the combination
+        if masks.len() - index < size - chosen.len() {
+            break;
+        }
+        chosen.push(index);
+        if find_combination(masks, full, size, index + 1, covered | masks[index], chosen) {
+            return true;
+        }
+        chosen.pop();
+    }
+    false
+}
+
+/** Takes candidate covering the most of uncovered groups, ties go to the one owning more files */
+fn greedy_cover(covers: &[Vec<bool>], files_counts: &[usize], groups_count: usize) -> Vec<usize> {
+    let mut uncovered = vec![true; groups_count];
+    let mut chosen: Vec<usize> = Vec::new();
+    while uncovered.iter().any(|group| *group) {
+        let best = (0..covers.len())
+            .map(|index| {
+                let gain = covers[index]
+                    .iter()
+                    .zip(&uncovered)
+                    .filter(|(covered, uncovered)| **covered && **uncovered)
+                    .count();
+                (gain, files_counts[index], index)
+            })
+            // the first candidate wins equal scores
+            .max_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)).then(b.2.cmp(&a.2)));
+        let index = match best {
+            Some((gain, _, index)) if gain > 0 => index,
+            _ => break,
+        };
+        for (group, covered) in covers[index].iter().enumerate() {
+            if *covered {
+                uncovered[group] = false;
+            }
+        }
+        chosen.push(index);
+    }
+    chosen
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::branch_diff::{group_changes_by_owners, parse_name_status};
+
+    const CODEOWNERS: &str = r"/a/ @alice @org/x
+/b/ @bob @org/x
+/c/ @carol @bob
+";
+
+    #[test]
+    fn chooses_the_smallest_set() {
+        let codeowners = codeowners::from_reader(CODEOWNERS.as_bytes());
+        let changes =
+            parse_name_status("M\0a/1.rs\0M\0b/2.rs\0M\0c/3.rs\0M\0c/4.rs\0A\0README.md\0");
+        let groups =
+            group_changes_by_owners(&changes, &codeowners, &codeowners, OwnersGrouping::Set);
+        let result = minimal_reviewers(&groups.iter().collect::<Vec<_>>());
+        assert!(result.exact);
+        assert_eq!(
+            result.reviewers,
+            vec![
+                ReviewerFiles {
+                    reviewer: Owner::Username("@alice".into()),
+                    files: vec!["a/1.rs".into()],
+                },
+                ReviewerFiles {
+                    reviewer: Owner::Username("@bob".into()),
+                    files: vec!["b/2.rs".into(), "c/3.rs".into(), "c/4.rs".into()],
+                },
+            ]
+        );
+        assert_eq!(result.unowned_files, vec!["README.md"]);
+    }
+
+    #[test]
+    fn greedy_and_exact_cover_every_group() {
+        let covers = vec![
+            vec![true, true, false, false],
+            vec![false, false, true, true],
+            vec![true, false, true, false],
+        ];
+        // greedy takes the candidate owning more files first and needs one more reviewer
+        assert_eq!(greedy_cover(&covers, &[2, 2, 5], 4), vec![2, 0, 1]);
+        assert_eq!(exact_cover(&covers, 4), vec![0, 1]);
+    }
+}
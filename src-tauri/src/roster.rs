@@ -0,0 +1,284 @@
+//! Roster of users, teams and emails known in the organization and validation of owners against it
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use codeowners::{Diagnostic, Owner, Owners};
+
+/**
+ * Known owners. Kinds which are missing in the roster are not validated,
+ * so roster listing only teams doesn't mark every username as unknown.
+ * GitHub names are case insensitive, so are comparisons with the roster.
+ */
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Default)]
+pub struct Roster {
+    /** Usernames with leading @ */
+    pub users: Option<Vec<String>>,
+    /** Team names like @org/team mapped to usernames of members */
+    pub teams: Option<BTreeMap<String, Vec<String>>>,
+    pub emails: Option<Vec<String>>,
+    /** Usernames mapped to commit emails and display names of the same person */
+    pub aliases: Option<BTreeMap<String, Vec<String>>>,
+}
+
+impl Roster {
+    /**
+     * Reads roster from a JSON or YAML file, format is chosen by extension.
+     * Relative path is resolved against the repository.
+     */
+    pub fn load(abs_repo_path: &str, roster_path: &str) -> Result<Roster, String> {
+        let path = Path::new(abs_repo_path).join(roster_path);
+        let content = fs::read_to_string(&path)
+            .map_err(|error| format!("Can't read roster {}: {error}", path.display()))?;
+        let result = if path.extension().and_then(|ext| ext.to_str()) == Some("json") {
+            serde_json::from_str(&content).map_err(|error| error.to_string())
+        } else {
+            serde_yaml::from_str(&content).map_err(|error| error.to_string())
+        };
+        result.map_err(|error| format!("Invalid roster {}: {error}", path.display()))
+    }
+
+    /** Usernames of team members. Empty for teams missing in the roster */
+    pub fn members_of(&self, team: &str) -> &[String] {
+        self.teams
+            .as_ref()
+            .and_then(|teams| {
+                teams
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(team))
+                    .map(|(_, members)| members.as_slice())
+            })
+            .unwrap_or_default()
+    }
+
+    /** Username which has `alias` among its emails or display names */
+    pub fn username_by_alias(&self, alias: &str) -> Option<&str> {
+        self.aliases
+            .as_ref()?
+            .iter()
+            .find(|(_, aliases)| {
+                aliases
+                    .iter()
+                    .any(|known| known.eq_ignore_ascii_case(alias))
+            })
+            .map(|(username, _)| username.as_str())
+    }
+
+    /**
+     * Usernames of owners with teams expanded into members, sorted without duplicates.
+     * Email owners are mapped with aliases and skipped if roster doesn't know them.
+     */
+    pub fn people_of(&self, owners: &[Owner]) -> Vec<String> {
+        let mut people: Vec<String> = Vec::new();
+        for owner in owners {
+            match owner {
+                Owner::Username(username) => people.push(username.clone()),
+                Owner::Team(team) => people.extend(self.members_of(team).iter().cloned()),
+                Owner::Email(email) => {
+                    people.extend(self.username_by_alias(email).map(String::from))
+                }
+            }
+        }
+        people.sort_by_key(|person| person.to_lowercase());
+        people.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+        people
+    }
+
+    /** True if owner is the person or a team the person is member of */
+    pub fn includes(&self, owner: &Owner, person: &str) -> bool {
+        match owner {
+            Owner::Username(username) => username.eq_ignore_ascii_case(person),
+            Owner::Team(team) => self
+                .members_of(team)
+                .iter()
+                .any(|member| member.eq_ignore_ascii_case(person)),
+            Owner::Email(email) => matches!(
+                self.username_by_alias(email),
+                Some(username) if username.eq_ignore_ascii_case(person)
+            ),
+        }
+    }
+
+    /** True if the name is the owner itself, e.g. a team or an email, or a person the owner includes */
+    pub fn is_owner(&self, owner: &Owner, name: &str) -> bool {
+        owner.to_string().eq_ignore_ascii_case(name) || self.includes(owner, name)
+    }
+
+    /** Known names of the same kind as `owner`. None if roster doesn't list this kind */
+    fn names_of_kind(&self, owner: &Owner) -> Option<Vec<&str>> {
+        match owner {
+            Owner::Username(_) => self
+                .users
+                .as_ref()
+                .map(|users| users.iter().map(String::as_str).collect()),
+            Owner::Team(_) => self
+                .teams
+                .as_ref()
+                .map(|teams| teams.keys().map(String::as_str).collect()),
+            Owner::Email(_) => self
+                .emails
+                .as_ref()
+                .map(|emails| emails.iter().map(String::as_str).collect()),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct UnknownOwner {
+    /** Line of CODEOWNERS starting from 1 */
+    pub line: usize,
+    pub owner: Owner,
+    /** Closest known owner of the same kind. Null if nothing is similar enough */
+    pub suggestion: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct OwnersValidation {
+    /** Lines which CODEOWNERS parser skipped */
+    pub diagnostics: Vec<Diagnostic>,
+    pub unknown_owners: Vec<UnknownOwner>,
+}
+
+impl OwnersValidation {
+    pub fn passed(&self) -> bool {
+        self.diagnostics.is_empty() && self.unknown_owners.is_empty()
+    }
+}
+
+/** Validates CODEOWNERS of the branch against the roster */
+pub fn validate_branch_owners(
+    abs_repo_path: &str,
+    branch: &str,
+    roster: &Roster,
+) -> OwnersValidation {
+    let content = crate::git::get_codeowners_content(abs_repo_path, branch);
+    validate_owners(&codeowners::from_reader(content.as_bytes()), roster)
+}
+
+/** Returns owners missing in the roster ordered by line */
+pub fn validate_owners(codeowners: &Owners, roster: &Roster) -> OwnersValidation {
+    let mut unknown_owners: Vec<UnknownOwner> = Vec::new();
+    for rule in codeowners.rules() {
+        for owner in &rule.owners {
+            let value = owner.to_string();
+            let names = match roster.names_of_kind(owner) {
+                Some(names) => names,
+                None => continue,
+            };
+            if names.iter().any(|name| name.eq_ignore_ascii_case(&value)) {
+                continue;
+            }
+            unknown_owners.push(UnknownOwner {
+                line: rule.line,
+                owner: owner.clone(),
+                suggestion: closest_name(&value, &names).map(String::from),
+            });
+        }
+    }
+    // rules are stored in precedence order, so the last line goes first
+    unknown_owners.sort_by_key(|unknown| unknown.line);
+    OwnersValidation {
+        diagnostics: codeowners.diagnostics().to_vec(),
+        unknown_owners,
+    }
+}
+
+/** Known name with the smallest edit distance if it differs in at most a third of characters */
+fn closest_name<'a>(value: &str, names: &[&'a str]) -> Option<&'a str> {
+    let value = value.to_lowercase();
+    let max_distance = (value.chars().count() / 3).max(1);
+    names
+        .iter()
+        .map(|name| (edit_distance(&value, &name.to_lowercase()), *name))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, name)| name)
+}
+
+/** Levenshtein distance counted in characters */
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROSTER: &str = r#"
+users:
+  - "@alice"
+  - "@bob"
+teams:
+  "@org/payments": ["@alice"]
+  "@org/core": []
+"#;
+
+    #[test]
+    fn parses_yaml_roster() {
+        let roster: Roster = serde_yaml::from_str(ROSTER).unwrap();
+        assert_eq!(roster.users, Some(vec!["@alice".into(), "@bob".into()]));
+        assert_eq!(roster.teams.unwrap()["@org/payments"], vec!["@alice"]);
+        assert_eq!(roster.emails, None);
+    }
+
+    #[test]
+    fn reports_unknown_owners_with_suggestions() {
+        let roster: Roster = serde_yaml::from_str(ROSTER).unwrap();
+        let codeowners = codeowners::from_reader(
+            "* @org/core\n/payments/ @org/paymnets @Alice\n/docs/ @carol docs@example.com\n"
+                .as_bytes(),
+        );
+        assert_eq!(
+            validate_owners(&codeowners, &roster).unknown_owners,
+            vec![
+                UnknownOwner {
+                    line: 2,
+                    owner: Owner::Team("@org/paymnets".into()),
+                    suggestion: Some("@org/payments".into()),
+                },
+                UnknownOwner {
+                    line: 3,
+                    owner: Owner::Username("@carol".into()),
+                    suggestion: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn expands_teams_into_members() {
+        let roster: Roster = serde_yaml::from_str(ROSTER).unwrap();
+        let owners = vec![
+            Owner::Team("@org/Payments".into()),
+            Owner::Username("@bob".into()),
+            Owner::Username("@alice".into()),
+            Owner::Team("@org/unknown".into()),
+        ];
+        assert_eq!(roster.people_of(&owners), vec!["@alice", "@bob"]);
+        let roster = Roster {
+            aliases: Some([("@carol".into(), vec!["carol@example.com".into()])].into()),
+            ..roster
+        };
+        assert!(roster.includes(&Owner::Email("Carol@example.com".into()), "@carol"));
+        assert!(roster.includes(&owners[0], "@Alice"));
+        assert!(!roster.includes(&owners[0], "@bob"));
+    }
+
+    #[test]
+    fn counts_edit_distance() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("same", "same"), 0);
+    }
+}
@@ -0,0 +1,330 @@
+//! Files, bytes and lines of a branch per owner, per extension and per owner and extension.
+//! Sizes and lines of files are cached per tree SHA inside of the git directory
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::git;
+use crate::{file_extension, normalize_owners};
+use codeowners::{Owner, Owners};
+
+/** Directory inside of the git directory where tree caches are kept */
+pub const CACHE_DIRECTORY: &str = "codeowners-viewer";
+/** Trees kept in each kind of cache, files written long ago are removed first */
+pub const MAX_CACHED_TREES: usize = 200;
+/** Git considers a file binary if its first 8000 bytes contain NUL */
+const BINARY_CHECK_BYTES: usize = 8000;
+
+/** Size and lines of a file of a tree */
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FileStats {
+    pub path: String,
+    pub bytes: u64,
+    /** None for binary files */
+    pub lines: Option<u64>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Default)]
+pub struct Totals {
+    pub files: u64,
+    pub bytes: u64,
+    /** Lines of text files */
+    pub lines: u64,
+    /** Binary files are counted in files and bytes, but not in lines */
+    pub binary_files: u64,
+}
+
+impl Totals {
+    fn add(&mut self, file: &FileStats) {
+        self.files += 1;
+        self.bytes += file.bytes;
+        match file.lines {
+            Some(lines) => self.lines += lines,
+            None => self.binary_files += 1,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct OwnerStats {
+    /** Null for unowned files */
+    pub owner: Option<Owner>,
+    #[serde(flatten)]
+    pub totals: Totals,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ExtensionStats {
+    /** Extension with leading dot, empty for files without extension */
+    pub extension: String,
+    #[serde(flatten)]
+    pub totals: Totals,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct OwnerExtensionStats {
+    pub owner: Option<Owner>,
+    pub extension: String,
+    #[serde(flatten)]
+    pub totals: Totals,
+}
+
+/**
+ * Statistics of a tree. File with several owners is counted for each of them,
+ * so sums of owner statistics can exceed the total. Lists are sorted by lines, the most first
+ */
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct RepoStatistics {
+    pub tree: String,
+    pub total: Totals,
+    pub owners: Vec<OwnerStats>,
+    pub extensions: Vec<ExtensionStats>,
+    pub owners_extensions: Vec<OwnerExtensionStats>,
+}
+
+/** Statistics of the branch with its own CODEOWNERS */
+pub fn get_repo_statistics(abs_repo_path: &str, branch: &str) -> RepoStatistics {
+    let tree = git::get_tree_sha(abs_repo_path, branch);
+    let files = get_tree_file_stats(abs_repo_path, &tree);
+    let codeowners_content = git::get_codeowners_content(abs_repo_path, branch);
+    let codeowners = codeowners::from_reader(codeowners_content.as_bytes());
+    repo_statistics(&tree, &files, &codeowners)
+}
+
+/**
+ * Sizes and lines of all files of the tree. The first call reads every blob,
+ * later calls for the same tree read the cache
+ */
+pub fn get_tree_file_stats(abs_repo_path: &str, tree: &str) -> Vec<FileStats> {
+    // revision doesn't exist, git already reported it
+    if tree.is_empty() {
+        return vec![];
+    }
+    let cache_path = git::get_git_path(
+        abs_repo_path,
+        &format!("{CACHE_DIRECTORY}/tree-{tree}.json"),
+    );
+    if let Some(files) = fs::read_to_string(&cache_path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+    {
+        return files;
+    }
+    let blobs = git::get_tree_blobs(abs_repo_path, tree);
+    let mut lines_by_sha: HashMap<String, Option<u64>> = HashMap::new();
+    let mut shas: Vec<String> = blobs.iter().map(|blob| blob.sha.clone()).collect();
+    shas.sort();
+    shas.dedup();
+    git::for_each_blob(abs_repo_path, shas, |sha, content| {
+        lines_by_sha.insert(sha.to_string(), count_lines(content));
+    });
+    let files: Vec<FileStats> = blobs
+        .into_iter()
+        .map(|blob| FileStats {
+            lines: lines_by_sha.get(&blob.sha).copied().flatten(),
+            path: blob.path,
+            bytes: blob.size,
+        })
+        .collect();
+    write_cache(&cache_path, &serde_json::to_string(&files).unwrap());
+    files
+}
+
+/**
+ * Writes a cache file named `<kind>-<tree>.json` and removes the oldest files of the same kind
+ * beyond `MAX_CACHED_TREES`. Cache is only an optimization, so failures are ignored
+ */
+pub fn write_cache(cache_path: &Path, content: &str) {
+    let directory = match cache_path.parent() {
+        Some(directory) => directory,
+        None => return,
+    };
+    fs::create_dir_all(directory).ok();
+    if fs::write(cache_path, content).is_err() {
+        return;
+    }
+    let name = cache_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let prefix = match name.find('-') {
+        Some(position) => &name[..=position],
+        None => return,
+    };
+    prune_cache(directory, prefix, MAX_CACHED_TREES);
+}
+
+/** Keeps `keep` most recently written files whose names start with `prefix` */
+pub fn prune_cache(directory: &Path, prefix: &str, keep: usize) {
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    let mut files: Vec<(SystemTime, PathBuf)> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(prefix))
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .collect();
+    if files.len() <= keep {
+        return;
+    }
+    files.sort_by_key(|(modified, _)| Reverse(*modified));
+    for (_, path) in &files[keep..] {
+        fs::remove_file(path).ok();
+    }
+}
+
+/** Count of lines like `wc -l` with the last line without line break. None for binary content */
+pub fn count_lines(content: &[u8]) -> Option<u64> {
+    if content[..content.len().min(BINARY_CHECK_BYTES)].contains(&0) {
+        return None;
+    }
+    let breaks = content.iter().filter(|byte| **byte == b'\n').count() as u64;
+    Some(match content.last() {
+        Some(b'\n') | None => breaks,
+        Some(_) => breaks + 1,
+    })
+}
+
+/** Aggregates file statistics with owners resolved by `codeowners` */
+pub fn repo_statistics(tree: &str, files: &[FileStats], codeowners: &Owners) -> RepoStatistics {
+    let mut total = Totals::default();
+    let mut owners: BTreeMap<Option<Owner>, Totals> = BTreeMap::new();
+    let mut extensions: BTreeMap<&str, Totals> = BTreeMap::new();
+    let mut owners_extensions: BTreeMap<(Option<Owner>, &str), Totals> = BTreeMap::new();
+    for file in files {
+        let extension = file_extension(&file.path);
+        total.add(file);
+        extensions.entry(extension).or_default().add(file);
+        let file_owners: Vec<Option<Owner>> = match normalize_owners(codeowners.of(&file.path)) {
+            file_owners if file_owners.is_empty() => vec![None],
+            file_owners => file_owners.into_iter().map(Some).collect(),
+        };
+        for owner in file_owners {
+            owners.entry(owner.clone()).or_default().add(file);
+            owners_extensions
+                .entry((owner, extension))
+                .or_default()
+                .add(file);
+        }
+    }
+    RepoStatistics {
+        tree: tree.to_string(),
+        total,
+        owners: sorted_by_lines(
+            owners
+                .into_iter()
+                .map(|(owner, totals)| OwnerStats { owner, totals })
+                .collect(),
+            |stats| stats.totals,
+        ),
+        extensions: sorted_by_lines(
+            extensions
+                .into_iter()
+                .map(|(extension, totals)| ExtensionStats {
+                    extension: extension.to_string(),
+                    totals,
+                })
+                .collect(),
+            |stats| stats.totals,
+        ),
+        owners_extensions: sorted_by_lines(
+            owners_extensions
+                .into_iter()
+                .map(|((owner, extension), totals)| OwnerExtensionStats {
+                    owner,
+                    extension: extension.to_string(),
+                    totals,
+                })
+                .collect(),
+            |stats| stats.totals,
+        ),
+    }
+}
+
+/** Sorts by lines, then by bytes. Stable sort keeps keys order for equal totals */
+fn sorted_by_lines<T, F>(mut items: Vec<T>, totals: F) -> Vec<T>
+where
+    F: Fn(&T) -> Totals,
+{
+    items.sort_by(|a, b| {
+        let (a, b) = (totals(a), totals(b));
+        (b.lines, b.bytes).cmp(&(a.lines, a.bytes))
+    });
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_lines_of_text_only() {
+        assert_eq!(count_lines(b""), Some(0));
+        assert_eq!(count_lines(b"a\nb\n"), Some(2));
+        assert_eq!(count_lines(b"a\nb"), Some(2));
+        assert_eq!(count_lines(b"\x89PNG\0\n"), None);
+    }
+
+    #[test]
+    fn aggregates_per_owner_and_extension() {
+        let codeowners = codeowners::from_reader("*.rs @alice\n/src/ @alice @bob\n".as_bytes());
+        let file = |path: &str, bytes: u64, lines: Option<u64>| FileStats {
+            path: path.into(),
+            bytes,
+            lines,
+        };
+        let files = vec![
+            file("src/main.rs", 100, Some(10)),
+            file("lib.rs", 50, Some(5)),
+            file("logo.png", 500, None),
+        ];
+        let statistics = repo_statistics("abc", &files, &codeowners);
+        assert_eq!(
+            statistics.total,
+            Totals {
+                files: 3,
+                bytes: 650,
+                lines: 15,
+                binary_files: 1,
+            }
+        );
+        let alice = Some(Owner::Username("@alice".into()));
+        let bob = Some(Owner::Username("@bob".into()));
+        assert_eq!(
+            statistics
+                .owners
+                .iter()
+                .map(|stats| (stats.owner.clone(), stats.totals.lines))
+                .collect::<Vec<_>>(),
+            vec![(alice.clone(), 15), (bob, 10), (None, 0)]
+        );
+        assert_eq!(statistics.extensions[0].extension, ".rs");
+        assert_eq!(statistics.extensions[0].totals.files, 2);
+        assert_eq!(statistics.owners_extensions[0].owner, alice);
+        assert_eq!(statistics.owners_extensions.len(), 3);
+    }
+
+    #[test]
+    fn prunes_only_caches_of_the_same_kind() {
+        let directory =
+            std::env::temp_dir().join(format!("codeowners-viewer-cache-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("coverage-a.json"), "{}").unwrap();
+        for tree in ["a", "b", "c"] {
+            fs::write(directory.join(format!("tree-{tree}.json")), "[]").unwrap();
+        }
+        prune_cache(&directory, "tree-", 2);
+        let mut names: Vec<String> = fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(names.len(), 3);
+        assert_eq!(names[0], "coverage-a.json");
+    }
+}
@@ -0,0 +1,184 @@
+//! Minimal mustache-like templates rendered with JSON data
+//!
+//! Supported tags: `{{name}}` inserts a value, `{{name.field}}` a field of an object value, `{{#name}}...{{/name}}` repeats the block for every
+//! item of a list or renders it once for other non-empty values, `{{^name}}...{{/name}}` renders
+//! the block for empty values. Lines which contain only a block tag are removed from the output.
+use serde_json::Value;
+
+#[derive(Debug, PartialEq)]
+enum Node {
+    Text(String),
+    Variable(String),
+    Block {
+        name: String,
+        inverted: bool,
+        children: Vec<Node>,
+    },
+}
+
+/** Renders the template with `data`. Error describes unbalanced or unclosed tags */
+pub fn render(template: &str, data: &Value) -> Result<String, String> {
+    let nodes = parse(&strip_standalone_tags(template))?;
+    let mut output = String::new();
+    render_nodes(&nodes, &mut vec![data], &mut output);
+    Ok(output)
+}
+
+/** Line with a single block tag keeps only the tag, so blocks don't leave empty lines */
+fn strip_standalone_tags(template: &str) -> String {
+    template
+        .split_inclusive('\n')
+        .map(|line| {
+            let trimmed = line.trim();
+            let is_block_tag = trimmed.starts_with("{{")
+                && trimmed.ends_with("}}")
+                && trimmed.matches("{{").count() == 1
+                && matches!(trimmed.chars().nth(2), Some('#') | Some('^') | Some('/'));
+            if is_block_tag {
+                trimmed
+            } else {
+                line
+            }
+        })
+        .collect()
+}
+
+fn parse(template: &str) -> Result<Vec<Node>, String> {
+    // every open block keeps its name, inverted flag and nodes parsed so far
+    let mut stack: Vec<(String, bool, Vec<Node>)> = vec![(String::new(), false, Vec::new())];
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let end = start
+            + rest[start..]
+                .find("}}")
+                .ok_or_else(|| format!("Unclosed tag {}", &rest[start..]))?;
+        let nodes = &mut stack.last_mut().unwrap().2;
+        if start > 0 {
+            nodes.push(Node::Text(rest[..start].to_string()));
+        }
+        let tag = rest[start + 2..end].trim();
+        match tag.chars().next() {
+            Some('#') | Some('^') => stack.push((
+                tag[1..].trim().to_string(),
+                tag.starts_with('^'),
+                Vec::new(),
+            )),
+            Some('/') => {
+                let (name, inverted, children) = stack.pop().unwrap();
+                if stack.is_empty() || name != tag[1..].trim() {
+                    return Err(format!("Unexpected closing tag {{{{{tag}}}}}"));
+                }
+                stack.last_mut().unwrap().2.push(Node::Block {
+                    name,
+                    inverted,
+                    children,
+                });
+            }
+            _ => nodes.push(Node::Variable(tag.to_string())),
+        }
+        rest = &rest[end + 2..];
+    }
+    if !rest.is_empty() {
+        stack
+            .last_mut()
+            .unwrap()
+            .2
+            .push(Node::Text(rest.to_string()));
+    }
+    if stack.len() > 1 {
+        return Err(format!(
+            "Unclosed block {{{{#{}}}}}",
+            stack.last().unwrap().0
+        ));
+    }
+    Ok(stack.pop().unwrap().2)
+}
+
+/** Looks up the first part of the name from the innermost context to the outermost one */
+fn lookup<'a>(context: &[&'a Value], name: &str) -> &'a Value {
+    if name == "." {
+        return context.last().unwrap();
+    }
+    let mut parts = name.split('.');
+    let first = parts.next().unwrap_or_default();
+    let value = context
+        .iter()
+        .rev()
+        .find_map(|value| value.get(first))
+        .unwrap_or(&Value::Null);
+    parts.fold(value, |value, part| value.get(part).unwrap_or(&Value::Null))
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Bool(value) => !value,
+        Value::String(value) => value.is_empty(),
+        Value::Array(items) => items.is_empty(),
+        Value::Number(_) | Value::Object(_) => false,
+    }
+}
+
+fn render_nodes(nodes: &[Node], context: &mut Vec<&Value>, output: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Variable(name) => match lookup(context, name) {
+                Value::Null => {}
+                Value::String(value) => output.push_str(value),
+                value => output.push_str(&value.to_string()),
+            },
+            Node::Block {
+                name,
+                inverted,
+                children,
+            } => {
+                let value = lookup(context, name);
+                if *inverted {
+                    if is_empty(value) {
+                        render_nodes(children, context, output);
+                    }
+                } else if let Value::Array(items) = value {
+                    for item in items {
+                        context.push(item);
+                        render_nodes(children, context, output);
+                        context.pop();
+                    }
+                } else if !is_empty(value) {
+                    context.push(value);
+                    render_nodes(children, context, output);
+                    context.pop();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn renders_variables_and_blocks() {
+        let data = json!({
+            "title": "Owners",
+            "total": { "files": 2 },
+            "groups": [{ "name": "@a", "files": ["x", "y"] }, { "name": "@b", "files": [] }],
+            "empty": [],
+        });
+        let template = "# {{title}} {{total.files}} {{total.missing}}\n{{#groups}}\n{{name}}:{{#files}} {{.}}{{/files}}{{^files}} none{{/files}}\n{{/groups}}\n{{^empty}}\nnothing\n{{/empty}}\n";
+        assert_eq!(
+            render(template, &data).unwrap(),
+            "# Owners 2 \n@a: x y\n@b: none\nnothing\n"
+        );
+    }
+
+    #[test]
+    fn reports_unbalanced_tags() {
+        assert!(render("{{#a}}", &json!({})).is_err());
+        assert!(render("{{#a}}{{/b}}", &json!({})).is_err());
+        assert!(render("{{/a}}", &json!({})).is_err());
+        assert!(render("{{a", &json!({})).is_err());
+    }
+}
@@ -0,0 +1,82 @@
+//! Exit codes of codeowners-viewer-cli, which pre-push scripts and CI jobs rely on
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/** Repository with `origin/main` and `feature` branch which adds a file nobody owns */
+fn repository(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "codeowners-viewer-cli-{name}-{}",
+        std::process::id()
+    ));
+    fs::remove_dir_all(&path).ok();
+    fs::create_dir_all(path.join("owned")).unwrap();
+    fs::write(path.join("CODEOWNERS"), "/owned/ @org/core\n").unwrap();
+    fs::write(path.join("owned/a.rs"), "fn a() {}\n").unwrap();
+    git(&path, &["init", "-q"]);
+    git(&path, &["add", "-A"]);
+    git(&path, &["commit", "-q", "-m", "Initial"]);
+    git(&path, &["update-ref", "refs/remotes/origin/main", "HEAD"]);
+    git(&path, &["checkout", "-q", "-b", "feature"]);
+    fs::write(path.join("owned/b.rs"), "fn b() {}\n").unwrap();
+    fs::write(path.join("loose.txt"), "nobody\n").unwrap();
+    git(&path, &["add", "-A"]);
+    git(&path, &["commit", "-q", "-m", "Add files"]);
+    path
+}
+
+fn git(path: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .current_dir(path)
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {args:?} failed");
+}
+
+fn exit_code(path: &Path, args: &[&str]) -> i32 {
+    Command::new(env!("CARGO_BIN_EXE_codeowners-viewer-cli"))
+        .args(args)
+        .arg("--repo")
+        .arg(path)
+        .output()
+        .unwrap()
+        .status
+        .code()
+        .unwrap()
+}
+
+#[test]
+fn check_fails_with_code_1_only_for_violations() {
+    let path = repository("check");
+    assert_eq!(exit_code(&path, &["check", "feature"]), 1);
+    assert_eq!(
+        exit_code(&path, &["check", "feature", "--allow-unowned"]),
+        0
+    );
+    git(&path, &["update-ref", "-d", "refs/remotes/origin/main"]);
+    // a missing base is a broken setup, not a passed or failed check
+    assert_eq!(
+        exit_code(&path, &["check", "feature", "--allow-unowned"]),
+        2
+    );
+    fs::remove_dir_all(&path).ok();
+}
+
+#[test]
+fn bad_input_exits_with_code_2() {
+    let path = repository("input");
+    assert_eq!(
+        exit_code(&path, &["trend", "feature", "--interval", "-1"]),
+        2
+    );
+    assert_eq!(
+        exit_code(&path, &["drift", "feature", "--months", "4294967296"]),
+        2
+    );
+    assert_eq!(exit_code(&path, &["unknown", "feature"]), 2);
+    assert_eq!(exit_code(&path, &["trend", "missing-branch"]), 2);
+    assert_eq!(exit_code(&path, &["trend", "feature"]), 0);
+    fs::remove_dir_all(&path).ok();
+}
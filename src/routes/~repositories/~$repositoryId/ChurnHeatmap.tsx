@@ -0,0 +1,84 @@
+import { type Churn, type ChurnReport } from '@/utils/churn';
+
+/** Columns beyond it are too narrow to read, groups are sorted by changed lines already */
+const MAX_OWNER_COLUMNS = 12;
+
+function churnTitle(churn: Churn) {
+  return `${churn.commits} commits, ${churn.lines} changed lines`;
+}
+
+const HeatCell: React.FC<{ churn: Churn; maxLines: number }> = ({ churn, maxLines }) => {
+  // square root keeps small teams visible next to the busiest ones
+  const heat = maxLines === 0 ? 0 : Math.sqrt(churn.lines / maxLines);
+  return (
+    <td
+      className='border border-neutral-200 dark:border-neutral-700 px-2 text-right tabular-nums'
+      style={{ backgroundColor: `rgba(239, 68, 68, ${heat * 0.8})` }}
+      title={churnTitle(churn)}
+    >
+      {churn.lines > 0 ? churn.lines : ''}
+    </td>
+  );
+};
+
+type Props = {
+  report: ChurnReport;
+};
+
+/** Changed lines per directory and owner group of recent history */
+export const ChurnHeatmap: React.FC<Props> = ({ report }) => {
+  const owners = report.owners.slice(0, MAX_OWNER_COLUMNS);
+  const maxLines = Math.max(
+    0,
+    ...report.directories.flatMap(directory =>
+      directory.owners.slice(0, MAX_OWNER_COLUMNS).map(churn => churn.lines),
+    ),
+  );
+  if (report.total.commits === 0) {
+    return <div>No changes in the last {report.months} months</div>;
+  }
+  return (
+    <div className='flex flex-col gap-2 overflow-x-auto'>
+      <span className='text-sm'>
+        {churnTitle(report.total)} in the last {report.months} months
+        {report.owners.length > owners.length &&
+          `, ${report.owners.length - owners.length} less changed owner groups are not shown`}
+      </span>
+      <table className='text-sm border-collapse'>
+        <thead>
+          <tr>
+            <th className='text-left px-2'>Directory</th>
+            <th className='text-right px-2'>Lines</th>
+            {owners.map(group => (
+              <th
+                key={group.owner}
+                className='px-2 font-normal text-left align-bottom [writing-mode:vertical-rl] rotate-180'
+                title={churnTitle(group)}
+              >
+                {group.owner || '(unowned)'}
+              </th>
+            ))}
+          </tr>
+        </thead>
+        <tbody>
+          {report.directories.map(directory => (
+            <tr key={directory.path}>
+              <td
+                className='px-2 font-mono whitespace-nowrap'
+                style={{ paddingLeft: `${directory.depth}rem` }}
+              >
+                {directory.path.split('/').pop()}/
+              </td>
+              <td className='px-2 text-right tabular-nums' title={churnTitle(directory)}>
+                {directory.lines}
+              </td>
+              {owners.map((group, index) => (
+                <HeatCell key={group.owner} churn={directory.owners[index]} maxLines={maxLines} />
+              ))}
+            </tr>
+          ))}
+        </tbody>
+      </table>
+    </div>
+  );
+};
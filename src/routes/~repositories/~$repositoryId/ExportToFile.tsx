@@ -1,83 +1,102 @@
 import { Button } from '@/components/ui/button';
-import { DirectoryOwners, FileOwners } from '@/utils/all-owners';
-import { open } from '@tauri-apps/api/dialog';
-import { writeFile, exists } from '@tauri-apps/api/fs';
-import { join } from '@tauri-apps/api/path';
+import { useAppConfig } from '@/app-config/useAppConfig';
+import { invoke } from '@tauri-apps/api';
+import { save } from '@tauri-apps/api/dialog';
 import { toast } from 'sonner';
 
 const FILENAME = 'codeowners';
 
-async function joinDir(parentDir: string, name: string): Promise<string> {
-  // handling empty string separately or else join adds an extra slash at the start
-  return parentDir === '' ? name : await join(parentDir, name);
-}
-
-async function getContent(filteredRoot: DirectoryOwners): Promise<string> {
-  /** Key os owner, value - list of paths */
-  const ownersMap = new Map<string, string[]>();
+type ExportFormat = 'csv' | 'json' | 'markdown' | 'html';
 
-  const addFiles = async (parentDirPath: string, files: FileOwners[]) => {
-    for (const file of files) {
-      let existingRecord = ownersMap.get(file.owner);
-      if (!existingRecord) {
-        existingRecord = [];
-        ownersMap.set(file.owner, existingRecord);
-      }
-      existingRecord.push(await joinDir(parentDirPath, file.name));
-    }
-  };
+/** Same filters as the tree view has. null means that everything passes */
+export type ExportFilter = {
+  owners: string[] | null;
+  /** Extensions with leading dot, empty string for files without extension */
+  extensions: string[] | null;
+};
 
-  const addDirectory = async (parentDirPath: string, dir: DirectoryOwners) => {
-    const currentDirPath = await joinDir(parentDirPath, dir.name);
-    await addFiles(currentDirPath, dir.files);
-    await addDirectories(currentDirPath, dir.directories);
-  };
+type ExportResult = {
+  path: string;
+  /** Count of exported files */
+  rows: number;
+};
 
-  const addDirectories = async (parentDirPath: string, dirs: DirectoryOwners[]) => {
-    for (const dir of dirs) {
-      await addDirectory(parentDirPath, dir);
-    }
-  };
+type ExportCommand =
+  | { command: 'export_all_codeowners_for_branch'; filter: ExportFilter }
+  | {
+      command: 'export_changed_codeowners_for_branch';
+      codeownersRevision: 'base' | 'head' | 'both';
+    };
 
-  await addFiles('', filteredRoot.files);
-  await addDirectories('', filteredRoot.directories);
-  return JSON.stringify(Object.fromEntries(ownersMap.entries()), null, 2);
+function formatOf(path: string): ExportFormat {
+  const lowerPath = path.toLowerCase();
+  if (lowerPath.endsWith('.json')) {
+    return 'json';
+  }
+  if (lowerPath.endsWith('.md')) {
+    return 'markdown';
+  }
+  if (lowerPath.endsWith('.html') || lowerPath.endsWith('.htm')) {
+    return 'html';
+  }
+  return 'csv';
 }
 
-async function createFileInSelectedDir(filteredRoot: DirectoryOwners) {
-  // Ask the user to pick a directory
-  const selectedDir = await open({
-    directory: true,
-    multiple: false,
-    title: 'Select a directory to save the file',
+async function exportToSelectedFile(
+  repoPath: string,
+  branch: string,
+  exportCommand: ExportCommand,
+) {
+  // Ask the user to pick a file, its extension chooses the format
+  const path = await save({
+    title: 'Select a file to export owners',
+    defaultPath: `${FILENAME}.csv`,
+    filters: [
+      { name: 'CSV', extensions: ['csv'] },
+      { name: 'JSON', extensions: ['json'] },
+      { name: 'Markdown', extensions: ['md'] },
+      { name: 'HTML', extensions: ['html'] },
+    ],
   });
-
-  if (!selectedDir || Array.isArray(selectedDir)) {
+  if (!path) {
     return;
   }
-  let path: string;
-  let index = 0;
-  do {
-    path = await join(selectedDir, `${FILENAME}${index === 0 ? '' : index}.json`);
-    index += 1;
-  } while (await exists(path));
-  const contents = await getContent(filteredRoot);
+  const { command, ...args } = exportCommand;
   try {
-    await writeFile({ path, contents });
-    toast.success(`File saved successfully to ${path}`);
+    // Backend writes rows straight to the file, so big trees are not passed through the webview
+    const result = JSON.parse(
+      (await invoke(command, {
+        ...args,
+        absRepoPath: repoPath,
+        branch,
+        format: formatOf(path),
+        outputPath: path,
+      })) as string,
+    ) as ExportResult;
+    toast.success(`${result.rows} files saved successfully to ${result.path}`);
   } catch (e) {
     toast.error(`Failed to save file: ${e}`);
   }
 }
 
 type Props = {
-  filteredRoot: DirectoryOwners;
+  branch: string;
+  exportCommand: ExportCommand;
 };
 
-export const ExportToFileButton: React.FC<Props> = ({ filteredRoot }) => {
+export const ExportToFileButton: React.FC<Props> = ({ branch, exportCommand }) => {
+  const appConfigResponse = useAppConfig();
+  const repoPath =
+    appConfigResponse.status === 'success'
+      ? appConfigResponse.data.repositories[0]?.repoPath
+      : null;
   return (
-    <Button variant='outline' onClick={() => createFileInSelectedDir(filteredRoot)}>
-      Export to json...
+    <Button
+      variant='outline'
+      disabled={!repoPath}
+      onClick={() => repoPath && exportToSelectedFile(repoPath, branch, exportCommand)}
+    >
+      Export to file...
     </Button>
   );
 };
@@ -22,13 +22,15 @@ import { Filter } from './Filter';
 import { getFileExtension } from './utils';
 import { ExportToFileButton } from './ExportToFile';
 import { AllCodeownersFetchProgress } from './AllCodeownersFetchProgress';
+import { ChurnHeatmap } from './ChurnHeatmap';
+import { useChurnReport } from '@/utils/churn';
 
 export const Route = createFileRoute('/repositories/$repositoryId/all-owners')({
   component: Codeowners,
 });
 
-function splitToOwners(owners: string | null): string[] {
-  return owners ? owners.replace(',', '').split(' ') : [];
+function ownerNames(file: FileOwners): string[] {
+  return file.owners.map(owner => owner.value);
 }
 
 /** filters files by passed predicate */
@@ -78,7 +80,7 @@ function useFilteredRoot(
     }
     let newRoot = root;
     if (filteredOwners !== null) {
-      newRoot = filterBy(newRoot, f => splitToOwners(f.owner).some(o => filteredOwners.has(o)));
+      newRoot = filterBy(newRoot, f => ownerNames(f).some(o => filteredOwners.has(o)));
     }
     if (filteredExtensions !== null) {
       newRoot = filterBy(newRoot, f => filteredExtensions.has(getFileExtension(f.name)));
@@ -104,16 +106,17 @@ function Codeowners() {
   const branchesResponse = useBranches();
 
   const updateBranchesList = useUpdateBranches();
+  const [churnShown, setChurnShown] = useState(false);
+  const churnResponse = useChurnReport(normalizedSelectedBranch, churnShown);
   /** null means all selected */
   const [filteredOwners, setFilteredOwners] = useState<Set<string> | null>(null);
   const allOwnersSet: Set<string> = useMemo(() => {
     const result: Set<string> = new Set();
     if (allCodeownersResponse.status === 'success' && allCodeownersResponse.data) {
       const addForDirectory = (dir: DirectoryOwners) => {
-        splitToOwners(dir.owner).forEach(o => result.add(o));
         dir.directories.forEach(d => addForDirectory(d));
         dir.files.forEach(file => {
-          splitToOwners(file.owner).forEach(o => result.add(o));
+          ownerNames(file).forEach(o => result.add(o));
         });
       };
       addForDirectory(allCodeownersResponse.data);
@@ -192,7 +195,25 @@ function Codeowners() {
                 entityName='file extension'
               />
             )}
-            {filteredRoot && <ExportToFileButton filteredRoot={filteredRoot} />}
+            {filteredRoot && normalizedSelectedBranch && (
+              <ExportToFileButton
+                branch={normalizedSelectedBranch}
+                exportCommand={{
+                  command: 'export_all_codeowners_for_branch',
+                  filter: {
+                    owners: filteredOwners && [...filteredOwners],
+                    extensions: filteredExtensions && [...filteredExtensions],
+                  },
+                }}
+              />
+            )}
+            {normalizedSelectedBranch && (
+              <Tooltip content='Changed lines of the last 3 months per directory and owner group'>
+                <Button variant='outline' onClick={() => setChurnShown(shown => !shown)}>
+                  {churnShown ? 'Show owners tree' : 'Show churn heatmap'}
+                </Button>
+              </Tooltip>
+            )}
           </div>
 
           <div className='flex gap-2 items-center'>
@@ -214,7 +235,16 @@ function Codeowners() {
           </div>
         </div>
       </div>
-      <div className='mt-4'>
+      {churnShown && (
+        <div className='mt-4'>
+          {churnResponse.status === 'pending' && <div>Reading history of the branch...</div>}
+          {churnResponse.status === 'error' && <div>Calculating churn error</div>}
+          {churnResponse.status === 'success' && churnResponse.data && (
+            <ChurnHeatmap report={churnResponse.data} />
+          )}
+        </div>
+      )}
+      <div className={churnShown ? 'hidden' : 'mt-4'}>
         {allCodeownersResponse.status === 'pending' && (
           <AllCodeownersFetchProgress branch={normalizedSelectedBranch} />
         )}
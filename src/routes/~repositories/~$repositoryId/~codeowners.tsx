@@ -3,7 +3,13 @@ import { createFileRoute, Navigate } from '@tanstack/react-router';
 import { useEffect, useState } from 'react';
 
 import { type AppConfig } from '../../../app-config/app-config';
-import { useBranchCodeowners, useUpdateBranchCodeowners } from '../../../utils/codeowners-command';
+import {
+  type ChangedFilesGroup,
+  type CodeownersRevision,
+  type OwnershipChange,
+  useBranchCodeowners,
+  useUpdateBranchCodeowners,
+} from '../../../utils/codeowners-command';
 import { useAppConfig } from '../../../app-config/useAppConfig';
 import { ComboboxOption, VirtualizedCombobox } from '@/components/ui/virtual-combobox';
 import { makeBranchOptions, useBranches, useUpdateBranches } from '@/utils/get-branches';
@@ -11,14 +17,88 @@ import { Button } from '@/components/ui/button';
 import { dayjs } from '@/utils/dayjs';
 import { Tooltip } from '@/components/ui/tooltip';
 import { RefreshIcon } from '@/components/icons/refresh-icon';
+import { type Owner } from '@/utils/all-owners';
+import { ExportToFileButton } from './ExportToFile';
 
 export const Route = createFileRoute('/repositories/$repositoryId/codeowners')({
   component: Codeowners,
 });
 
+const REVISION_OPTIONS: { value: CodeownersRevision; label: string; tooltip: string }[] = [
+  {
+    value: 'base',
+    label: 'Base',
+    tooltip:
+      'CODEOWNERS of the merge-base, the commit the branch was forked from. GitHub uses the tip of the base branch, so it differs once main moves on',
+  },
+  { value: 'head', label: 'Branch', tooltip: 'CODEOWNERS of the branch itself' },
+  { value: 'both', label: 'Both', tooltip: 'Base and branch CODEOWNERS side by side' },
+];
+
+const RevisionSelector: React.FC<{
+  revision: CodeownersRevision;
+  revisionChanged: (revision: CodeownersRevision) => void;
+}> = ({ revision, revisionChanged }) => (
+  <div className='flex gap-1 items-center'>
+    {REVISION_OPTIONS.map(option => (
+      <Tooltip key={option.value} content={option.tooltip}>
+        <Button
+          variant={option.value === revision ? 'secondary' : 'ghost'}
+          onClick={() => revisionChanged(option.value)}
+        >
+          {option.label}
+        </Button>
+      </Tooltip>
+    ))}
+  </div>
+);
+
+const OwnersGroups: React.FC<{ title: string; groups: Map<string, ChangedFilesGroup> }> = ({
+  title,
+  groups,
+}) => (
+  <div className='flex flex-col gap-1'>
+    <span className='text-sm font-semibold'>{title}</span>
+    {[...groups.entries()].map(([label, group]) => (
+      <div key={label} className='flex flex-col'>
+        <span className='text-sm'>
+          <span className='font-mono'>{label || '(unowned)'}</span>: {group.files.length} files, +
+          {group.addedLines} / -{group.deletedLines}
+        </span>
+        <pre className='text-sm text-neutral-900 dark:text-neutral-400'>
+          {JSON.stringify(group.files, null, 2)}
+        </pre>
+      </div>
+    ))}
+  </div>
+);
+
+function ownersLabel(owners: Owner[]) {
+  return owners.length === 0 ? '(unowned)' : owners.map(owner => owner.value).join(', ');
+}
+
+const OwnershipChanged: React.FC<{ changes: OwnershipChange[] }> = ({ changes }) => (
+  <div className='flex flex-col gap-1'>
+    <span className='text-sm font-semibold'>Ownership changed by this branch:</span>
+    {changes.length === 0 ? (
+      <span className='text-sm'>Branch doesn't change owners of any file</span>
+    ) : (
+      <ul className='text-sm text-neutral-900 dark:text-neutral-400'>
+        {changes.map(change => (
+          <li key={change.path}>
+            <span className='font-mono'>{change.path}</span>: {ownersLabel(change.base_owners)} →{' '}
+            {ownersLabel(change.head_owners)}
+          </li>
+        ))}
+      </ul>
+    )}
+  </div>
+);
+
 function Codeowners() {
   const [branchOptions, setBranchOptions] = useState<ComboboxOption[]>([]);
   const [selectedBranchOption, setSelectedBranchOption] = useState<ComboboxOption | null>(null);
+  const [revision, setRevision] = useState<CodeownersRevision>('head');
   const appConfigResponse = useAppConfig();
   const appConfig: AppConfig | undefined = appConfigResponse.data;
 
@@ -26,7 +106,7 @@ function Codeowners() {
   const updateBranchesList = useUpdateBranches();
 
   const normalizedSelectedBranch = selectedBranchOption?.value ?? null;
-  const branchCodeownersResponse = useBranchCodeowners(normalizedSelectedBranch);
+  const branchCodeownersResponse = useBranchCodeowners(normalizedSelectedBranch, revision);
   const updateBranchCodeowners = useUpdateBranchCodeowners(normalizedSelectedBranch);
 
   useEffect(() => {
@@ -60,14 +140,26 @@ function Codeowners() {
         </span>
 
         <div className='flex gap-2 justify-between mt-2 mb-6'>
-          <VirtualizedCombobox
-            options={branchOptions}
-            selectedOption={selectedBranchOption}
-            selectedChanged={setSelectedBranchOption}
-            searchPlaceholder='Select branch ...'
-            height='400px'
-            disabled={branchesResponse.status !== 'success'}
-          />
+          <div className='flex gap-2'>
+            <VirtualizedCombobox
+              options={branchOptions}
+              selectedOption={selectedBranchOption}
+              selectedChanged={setSelectedBranchOption}
+              searchPlaceholder='Select branch ...'
+              height='400px'
+              disabled={branchesResponse.status !== 'success'}
+            />
+            <RevisionSelector revision={revision} revisionChanged={setRevision} />
+            {normalizedSelectedBranch && (
+              <ExportToFileButton
+                branch={normalizedSelectedBranch}
+                exportCommand={{
+                  command: 'export_changed_codeowners_for_branch',
+                  codeownersRevision: revision,
+                }}
+              />
+            )}
+          </div>
 
           <div className='flex gap-2 items-center'>
             <Tooltip content='Update branches list'>
@@ -115,9 +207,21 @@ function Codeowners() {
                 <RefreshIcon className='[animation-duration:2500ms]' />
               </Button>
             </Tooltip>
-            <pre className='text-sm text-neutral-900 dark:text-neutral-400'>
-              {JSON.stringify(Object.fromEntries(branchCodeownersResponse.data.entries()), null, 2)}
-            </pre>
+            <div className='flex flex-col gap-4'>
+              {branchCodeownersResponse.data.base && (
+                <OwnersGroups
+                  title='Owners by base CODEOWNERS:'
+                  groups={branchCodeownersResponse.data.base}
+                />
+              )}
+              {branchCodeownersResponse.data.head && (
+                <OwnersGroups
+                  title='Owners by branch CODEOWNERS:'
+                  groups={branchCodeownersResponse.data.head}
+                />
+              )}
+              <OwnershipChanged changes={branchCodeownersResponse.data.ownershipChanged} />
+            </div>
           </div>
         </div>
       )}
@@ -6,10 +6,18 @@ import { useCallback, useEffect } from 'react';
 import { listen } from '@tauri-apps/api/event';
 import { useWebViewSessionId } from './WebViewSessionIdProvider';
 
+export type Owner = {
+  kind: 'username' | 'team' | 'email';
+  value: string;
+};
+
 export type FileOwners = {
   /** File name */
   name: string;
+  /** Owners joined with ", ". Empty for unowned files */
   owner: string;
+  /** Owners sorted by kind and name */
+  owners: Owner[];
 };
 
 export type DirectoryOwners = {
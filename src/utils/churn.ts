@@ -0,0 +1,61 @@
+import { Repositories } from '@/app-config/app-config';
+import { useAppConfig } from '@/app-config/useAppConfig';
+import { useQuery } from '@tanstack/react-query';
+import { invoke } from '@tauri-apps/api';
+import { Owner } from './all-owners';
+
+export type Churn = {
+  /** Commits are counted once even if they change several files */
+  commits: number;
+  /** Added and deleted lines, a change of a binary file counts as one line */
+  lines: number;
+};
+
+export type OwnerGroupChurn = Churn & {
+  /** Owners joined with ", " like in the tree. Empty for unowned files */
+  owner: string;
+  owners: Owner[];
+};
+
+export type DirectoryChurn = Churn & {
+  /** Path without trailing slash */
+  path: string;
+  /** 1 for top-level directories */
+  depth: number;
+  /** Cells of the heatmap row, in order of owner groups of the report */
+  owners: Churn[];
+};
+
+export type ChurnReport = {
+  branch: string;
+  months: number;
+  total: Churn;
+  /** Owner groups which had changes, the most changed lines first */
+  owners: OwnerGroupChurn[];
+  /** Directories down to the requested depth, parents before children */
+  directories: DirectoryChurn[];
+};
+
+async function getChurnReport(repository: Repositories, branch: string): Promise<ChurnReport> {
+  const report = (await invoke('get_churn_report_for_branch', {
+    branch,
+    absRepoPath: repository.repoPath,
+  })) as string;
+  return JSON.parse(report) as ChurnReport;
+}
+
+/** Churn is read from history only when `enabled`, it walks git log of the last months */
+export function useChurnReport(branch: string | null, enabled: boolean) {
+  const appConfigResponse = useAppConfig();
+
+  return useQuery({
+    queryKey: ['branch', branch ?? '', 'churn'],
+    queryFn: () =>
+      appConfigResponse.status === 'success'
+        ? getChurnReport(appConfigResponse.data.repositories[0], branch!)
+        : null,
+    enabled: enabled && !!branch && appConfigResponse.status === 'success',
+    staleTime: 1_000 * 60 * 60, // history of months doesn't change much within an hour
+    refetchOnWindowFocus: false,
+  });
+}
@@ -3,36 +3,107 @@ import { invoke } from '@tauri-apps/api';
 import { useAppConfig } from '@/app-config/useAppConfig';
 import { useQuery, useQueryClient } from '@tanstack/react-query';
 import { useCallback } from 'react';
+import { Owner } from './all-owners';
+
+export type ChangedFile = {
+  status: 'added' | 'modified' | 'deleted' | 'renamed' | 'copied';
+  path: string;
+  /** Path before rename or copy */
+  old_path: string | null;
+  /** Owners of the old path on the base. Set only for renamed files */
+  old_owners: Owner[] | null;
+  added_lines: number;
+  deleted_lines: number;
+  /** Lines are not counted for binary files */
+  binary: boolean;
+};
+
+type OwnersGroup = {
+  /** Owners sorted by kind and name. Empty for unowned files */
+  owners: Owner[];
+  /** Owners joined with ", " */
+  label: string;
+  files: ChangedFile[];
+  added_lines: number;
+  deleted_lines: number;
+};
+
+export type CodeownersRevision = 'base' | 'head' | 'both';
+
+export type OwnershipChange = { path: string; base_owners: Owner[]; head_owners: Owner[] };
+
+type BranchCodeowners = {
+  /** Changed files grouped with base CODEOWNERS */
+  base: OwnersGroup[] | null;
+  /** Changed files grouped with branch CODEOWNERS */
+  head: OwnersGroup[] | null;
+  /** Files whose owners are changed by the branch itself */
+  ownership_changed: OwnershipChange[];
+};
+
+/** Changed files of one owners group with their line totals */
+export type ChangedFilesGroup = {
+  files: ChangedFile[];
+  addedLines: number;
+  deletedLines: number;
+};
+
+export type BranchDifference = {
+  /** Changed files by owners of base CODEOWNERS. null unless base or both revisions requested */
+  base: Map<string, ChangedFilesGroup> | null;
+  /** Changed files by owners of branch CODEOWNERS. null unless head or both revisions requested */
+  head: Map<string, ChangedFilesGroup> | null;
+  /** Files whose owners are changed by the branch itself */
+  ownershipChanged: OwnershipChange[];
+};
+
+function groupsToMap(groups: OwnersGroup[] | null): Map<string, ChangedFilesGroup> | null {
+  return (
+    groups?.reduce((acc, item) => {
+      acc.set(item.label, {
+        files: item.files,
+        addedLines: item.added_lines,
+        deletedLines: item.deleted_lines,
+      });
+      return acc;
+    }, new Map<string, ChangedFilesGroup>()) ?? null
+  );
+}
 
 async function getBranchDifference(
   repository: Repositories,
   branch: string,
-): Promise<null | Map<string, string[]>> {
+  codeownersRevision: CodeownersRevision,
+): Promise<BranchDifference> {
   const owners = (await invoke('get_changed_codeowners_for_branch', {
     branch,
     absRepoPath: repository.repoPath,
+    codeownersRevision,
   })) as string;
   // We pass it as list in order to get always the same data in the same order.
-  const parsedOwners = JSON.parse(owners) as { owners: string; files: string[] }[];
+  const parsedOwners = JSON.parse(owners) as BranchCodeowners;
 
-  return parsedOwners.reduce((acc, item) => {
-    acc.set(item.owners, item.files);
-    return acc;
-  }, new Map<string, string[]>());
+  return {
+    base: groupsToMap(parsedOwners.base),
+    head: groupsToMap(parsedOwners.head),
+    ownershipChanged: parsedOwners.ownership_changed,
+  };
 }
 
-function getBranchCodeownersQueryKey(branch: string | null) {
-  return ['branch', branch ?? '', 'codeowners'];
+/** Without revision it matches queries of every revision */
+function getBranchCodeownersQueryKey(branch: string | null, revision?: CodeownersRevision) {
+  const key = ['branch', branch ?? '', 'codeowners'];
+  return revision ? [...key, revision] : key;
 }
 
-export function useBranchCodeowners(branch: string | null) {
+export function useBranchCodeowners(branch: string | null, revision: CodeownersRevision) {
   const appConfigResponse = useAppConfig();
 
   const result = useQuery({
-    queryKey: getBranchCodeownersQueryKey(branch),
+    queryKey: getBranchCodeownersQueryKey(branch, revision),
     queryFn: () =>
       appConfigResponse.status === 'success'
-        ? getBranchDifference(appConfigResponse.data.repositories[0], branch!)
+        ? getBranchDifference(appConfigResponse.data.repositories[0], branch!, revision)
         : null,
     enabled: !!branch && appConfigResponse.status === 'success',
     refetchInterval: 1_000 * 60 * 5, // every 5 min